@@ -92,7 +92,8 @@ function query() {
             var json = JSON.parse(xhr.responseText);
             if (json.path != "") {
                 printPath(json);
-                show_result(json.features[0].properties.weight);
+                var properties = json.features[0].properties;
+                show_result(properties.weight, properties.roads, properties.elevations, properties.ascent, properties.ferry);
             } else {
                 show_no_path_found();
             }
@@ -171,9 +172,18 @@ function hide_select_start_and_end() {
     }
 }
 
-function show_result(costs) {
+function show_result(costs, roads, elevations, ascent, ferry) {
     var tmp = document.getElementById("result")
-    tmp.innerHTML = costs;
+    tmp.innerText = costs;
+    if (ferry) {
+        tmp.innerText += " (includes a ferry)";
+    }
+    if (elevations && elevations.length > 0) {
+        tmp.innerText += ", ascent " + ascent.toFixed(0) + " m";
+    }
+    if (roads && roads.length > 0) {
+        tmp.innerText += "\n" + roads.join(", ");
+    }
     tmp.style.display = "block";
 }
 
@@ -0,0 +1,126 @@
+use super::*;
+use bincode::{deserialize_from, serialize_into};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::time::{Duration, UNIX_EPOCH};
+
+/// name of the cached graph next to the output file, e.g. `germany.car-time.cache`
+pub fn get_cache_filename(output_file: &str) -> String {
+    format!("{}.cache", output_file.trim_end_matches(".fmi"))
+}
+
+/// checkpoints of the contraction next to the output file, e.g. `germany.car-time.checkpoint`
+pub fn get_checkpoints(output_file: &str) -> contraction::CheckpointSettings {
+    contraction::CheckpointSettings {
+        filename: format!("{}.checkpoint", output_file.trim_end_matches(".fmi")),
+        interval: Duration::from_secs(CHECKPOINT_SECONDS),
+    }
+}
+
+/// identifies the input and all settings the weighted graph depends on.
+/// the input file is compared by its size and modification time
+pub fn get_cache_key(
+    arguments: &helper::Arguments,
+    profile: &Profile,
+    optimize_by: OptimizeBy,
+) -> String {
+    let metadata = std::fs::metadata(&arguments.filename).ok();
+    let size = metadata.as_ref().map(|m| m.len());
+    let modified = (metadata.and_then(|m| m.modified().ok()))
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok());
+    format!(
+        "{} {} {:?} {:?} {} {} {:?} {} {} {:?}",
+        env!("CARGO_PKG_VERSION"),
+        arguments.filename,
+        size,
+        modified,
+        serde_json::to_string(profile).unwrap(),
+        optimize_by,
+        arguments.clip_area,
+        arguments.complete_ways,
+        arguments.id_mapping,
+        arguments.elevation,
+    )
+}
+
+/// check if the cached graph belongs to the key, only the key is read
+pub fn is_valid(filename: &str, key: &str) -> bool {
+    let file = match File::open(filename) {
+        Ok(file) => file,
+        Err(_) => return false,
+    };
+    let cached_key: Result<String, _> = deserialize_from(&mut BufReader::new(file));
+    cached_key.is_ok_and(|cached_key| cached_key == key)
+}
+
+/// check if there are valid cached graphs for all profiles and metrics
+pub fn is_cached(arguments: &helper::Arguments) -> bool {
+    arguments.profiles.iter().all(|profile| {
+        arguments.optimize_by.iter().all(|optimize_by| {
+            let output_file =
+                helper::get_output_filename(&arguments.filename, profile, *optimize_by);
+            let key = get_cache_key(arguments, profile, *optimize_by);
+            is_valid(&get_cache_filename(&output_file), &key)
+        })
+    })
+}
+
+pub fn read_graph(filename: &str) -> WeightedGraph {
+    let file = File::open(filename).unwrap();
+    match deserialize_from(&mut BufReader::new(file)) {
+        Ok(graph) => graph,
+        Err(e) => {
+            println!("could not read {}: {}", filename, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+pub fn write_graph(filename: &str, graph: &WeightedGraph) {
+    let mut writer = BufWriter::new(File::create(filename).unwrap());
+    serialize_into(&mut writer, graph).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cached_graph() {
+        let filename = std::env::temp_dir().join("osm_ch_pre-cached_graph.cache");
+        let filename = filename.to_str().unwrap();
+        let node = Node {
+            latitude: 48.0,
+            longitude: 9.0,
+            rank: INVALID_RANK,
+        };
+        let graph = WeightedGraph {
+            key: "small.osm car time".to_string(),
+            nodes: vec![node; 2],
+            edges: vec![Way::new(0, 1, 5), Way::new(1, 0, 7)],
+            edge_ids: vec![0, 1],
+            original_nodes: Vec::new(),
+            optimized_by: OptimizeBy::Time,
+            geometry: Geometry::default(),
+            edge_ways: vec![0, 0],
+            ways: Vec::new(),
+            strings: vec!["Nordstraße".to_string()],
+        };
+        write_graph(filename, &graph);
+        assert!(is_valid(filename, &graph.key));
+        assert!(!is_valid(filename, "small.osm car distance"));
+        assert!(!is_valid("missing.cache", &graph.key));
+
+        let cached = read_graph(filename);
+        assert_eq!(cached.edges, graph.edges);
+        assert_eq!(cached.edge_ids, graph.edge_ids);
+        assert_eq!(cached.strings, graph.strings);
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn filenames() {
+        assert_eq!(get_cache_filename("ny.car-time.fmi"), "ny.car-time.cache");
+        assert_eq!(get_checkpoints("ny.fmi").filename, "ny.checkpoint");
+    }
+}
@@ -0,0 +1,253 @@
+use super::*;
+
+/// check if two consecutive edges can be merged without losing information
+fn is_mergeable(a: &OsmWay, b: &OsmWay) -> bool {
+    a.speed == b.speed
+        && a.speed_factor == b.speed_factor
+        && a.penalty == b.penalty
+        && a.way == b.way
+}
+
+/// find the nodes of degree 2, which are only passed through.
+/// every incoming edge has to continue with exactly one outgoing edge to the other neighbor
+fn get_chain_nodes(
+    full_edges: &[OsmWay],
+    incoming: &[Vec<EdgeId>],
+    outgoing: &[Vec<EdgeId>],
+    protected: &[bool],
+) -> Vec<bool> {
+    (0..incoming.len())
+        .into_par_iter()
+        .map(|node| {
+            let (incoming, outgoing) = (&incoming[node], &outgoing[node]);
+            if protected[node] || incoming.len() != outgoing.len() || incoming.len() > 2 {
+                return false;
+            }
+            let mut neighbors: Vec<NodeId> = incoming
+                .iter()
+                .map(|edge| full_edges[*edge].source)
+                .chain(outgoing.iter().map(|edge| full_edges[*edge].target))
+                .collect();
+            neighbors.sort_unstable();
+            neighbors.dedup();
+            if neighbors.len() != 2 || neighbors.contains(&node) {
+                return false;
+            }
+            incoming.iter().all(|from| {
+                let mut next = outgoing
+                    .iter()
+                    .filter(|to| full_edges[**to].target != full_edges[*from].source);
+                match (next.next(), next.next()) {
+                    (Some(to), None) => is_mergeable(&full_edges[*from], &full_edges[*to]),
+                    _ => false,
+                }
+            })
+        })
+        .collect()
+}
+
+/// merge the edge with the following edges until the next node, which is not part of a chain.
+/// returns the merged edge and the passed chain nodes
+fn follow_chain(
+    first_edge: &OsmWay,
+    full_edges: &[OsmWay],
+    outgoing: &[Vec<EdgeId>],
+    chain_nodes: &[bool],
+) -> (OsmWay, Vec<NodeId>) {
+    let mut edge = *first_edge;
+    let mut passed = Vec::<NodeId>::new();
+    let mut previous = edge.source;
+    while chain_nodes[edge.target] {
+        let node = edge.target;
+        passed.push(node);
+        let next = outgoing[node]
+            .iter()
+            .map(|next| &full_edges[*next])
+            .find(|next| next.target != previous)
+            .unwrap();
+        previous = node;
+        edge.target = next.target;
+        edge.distance += next.distance;
+        edge.ascent += next.ascent;
+        edge.descent += next.descent;
+        edge.delay += next.delay;
+        edge.duration = edge.duration.zip(next.duration).map(|(a, b)| a + b);
+    }
+    (edge, passed)
+}
+
+/// collapse chains of degree-2 nodes into single edges.
+/// the coordinates of the removed nodes are returned as geometry of the new edges.
+/// protected nodes are kept, closed chains without any junction are dropped.
+/// loops back to their junction are split at one of their nodes
+pub fn compress_chains(
+    full_edges: &mut Vec<OsmWay>,
+    nodes: &[Node],
+    protected: &[bool],
+) -> Geometry {
+    let mut incoming = vec![Vec::<EdgeId>::new(); nodes.len()];
+    let mut outgoing = vec![Vec::<EdgeId>::new(); nodes.len()];
+    for (i, edge) in full_edges.iter().enumerate() {
+        incoming[edge.target].push(i);
+        outgoing[edge.source].push(i);
+    }
+    let mut chain_nodes = get_chain_nodes(full_edges, &incoming, &outgoing, protected);
+
+    // the lowest node of a loop is kept, so both directions split it at the same node
+    for first_edge in full_edges.iter() {
+        if chain_nodes[first_edge.source] {
+            continue;
+        }
+        let (edge, passed) = follow_chain(first_edge, full_edges, &outgoing, &chain_nodes);
+        if edge.source == edge.target {
+            if let Some(node) = passed.iter().min() {
+                chain_nodes[*node] = false;
+            }
+        }
+    }
+
+    let mut result = Vec::<OsmWay>::new();
+    let mut geometry = Geometry::default();
+    for first_edge in full_edges.iter() {
+        if chain_nodes[first_edge.source] {
+            continue;
+        }
+        let (edge, passed) = follow_chain(first_edge, full_edges, &outgoing, &chain_nodes);
+        let coordinates: Vec<(f32, f32)> = (passed.iter())
+            .map(|node| (nodes[*node].latitude, nodes[*node].longitude))
+            .collect();
+        result.push(edge);
+        geometry.push(&coordinates);
+    }
+    *full_edges = result;
+    geometry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(latitude: f32, longitude: f32) -> Node {
+        Node {
+            latitude,
+            longitude,
+            rank: INVALID_RANK,
+        }
+    }
+
+    fn edge(source: NodeId, target: NodeId, way: WayId) -> OsmWay {
+        OsmWay {
+            source,
+            target,
+            speed: 50,
+            distance: 10,
+            penalty: 0,
+            way,
+            ascent: 0,
+            descent: 0,
+            ferry: false,
+            delay: 0,
+            speed_factor: 100,
+            duration: None,
+        }
+    }
+
+    fn both(source: NodeId, target: NodeId, way: WayId) -> Vec<OsmWay> {
+        vec![edge(source, target, way), edge(target, source, way)]
+    }
+
+    #[test]
+    fn chains() {
+        // 0 - 1 - 2 - 3 -> 4 -> 5
+        //         |
+        //         6
+        let nodes: Vec<Node> = (0..7).map(|i| node(i as f32, 0.0)).collect();
+        let mut full_edges: Vec<OsmWay> = [both(0, 1, 1), both(1, 2, 1), both(2, 3, 1)]
+            .concat()
+            .into_iter()
+            .chain(vec![edge(3, 4, 1), edge(4, 5, 1)])
+            .chain(both(2, 6, 2))
+            .collect();
+        let geometry = compress_chains(&mut full_edges, &nodes, &[false; 7]);
+
+        let mut result: Vec<(NodeId, NodeId, usize)> = full_edges
+            .iter()
+            .map(|e| (e.source, e.target, e.distance))
+            .collect();
+        result.sort_unstable();
+        assert_eq!(
+            result,
+            vec![
+                (0, 2, 20),
+                (2, 0, 20),
+                (2, 3, 10),
+                (2, 6, 10),
+                (3, 2, 10),
+                (3, 5, 20),
+                (6, 2, 10)
+            ]
+        );
+        let index = full_edges
+            .iter()
+            .position(|e| e.source == 2 && e.target == 0);
+        assert_eq!(geometry.get(index.unwrap()), &[(1.0, 0.0)]);
+        let index = full_edges
+            .iter()
+            .position(|e| e.source == 3 && e.target == 5);
+        assert_eq!(geometry.get(index.unwrap()), &[(4.0, 0.0)]);
+    }
+
+    #[test]
+    fn keep_nodes() {
+        // 0 - 1 - 2 - 3 with node 2 protected and a different way from 1 on
+        let nodes: Vec<Node> = (0..4).map(|i| node(i as f32, 0.0)).collect();
+        let mut full_edges = [both(0, 1, 1), both(1, 2, 2), both(2, 3, 2)].concat();
+        let geometry = compress_chains(&mut full_edges, &nodes, &[false, false, true, false]);
+        assert_eq!(full_edges.len(), 6);
+        assert!((0..6).all(|i| geometry.get(i).is_empty()));
+
+        // closed chains are dropped
+        let mut full_edges = vec![edge(0, 1, 1), edge(1, 2, 1), edge(2, 0, 1)];
+        compress_chains(&mut full_edges, &nodes, &[false; 4]);
+        assert!(full_edges.is_empty());
+    }
+
+    #[test]
+    fn split_loops() {
+        // 0 - 1 - 2
+        //     |   |
+        //     4 - 3
+        let nodes: Vec<Node> = (0..5).map(|i| node(i as f32, 0.0)).collect();
+        let mut full_edges = [
+            both(0, 1, 1),
+            both(1, 2, 2),
+            both(2, 3, 2),
+            both(3, 4, 2),
+            both(4, 1, 2),
+        ]
+        .concat();
+        let geometry = compress_chains(&mut full_edges, &nodes, &[false; 5]);
+
+        // the loop at the junction 1 is split at its node 2
+        let mut result: Vec<(NodeId, NodeId, usize)> = full_edges
+            .iter()
+            .map(|e| (e.source, e.target, e.distance))
+            .collect();
+        result.sort_unstable();
+        assert_eq!(
+            result,
+            vec![
+                (0, 1, 10),
+                (1, 0, 10),
+                (1, 2, 10),
+                (1, 2, 30),
+                (2, 1, 10),
+                (2, 1, 30)
+            ]
+        );
+        let index = full_edges
+            .iter()
+            .position(|e| e.source == 1 && e.distance == 30);
+        assert_eq!(geometry.get(index.unwrap()), &[(4.0, 0.0), (3.0, 0.0)]);
+    }
+}
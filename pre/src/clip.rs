@@ -0,0 +1,225 @@
+use super::*;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// area the graph is clipped to
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClipArea {
+    BoundingBox {
+        lat_min: f32,
+        lat_max: f32,
+        lng_min: f32,
+        lng_max: f32,
+    },
+    /// rings of (longitude, latitude), holes are excluded
+    Polygon {
+        outer: Vec<Vec<(f32, f32)>>,
+        inner: Vec<Vec<(f32, f32)>>,
+    },
+}
+
+impl ClipArea {
+    pub fn contains(&self, lat: f32, lng: f32) -> bool {
+        match self {
+            ClipArea::BoundingBox {
+                lat_min,
+                lat_max,
+                lng_min,
+                lng_max,
+            } => lat >= *lat_min && lat <= *lat_max && lng >= *lng_min && lng <= *lng_max,
+            ClipArea::Polygon { outer, inner } => {
+                outer.iter().any(|ring| ring_contains(ring, lat, lng))
+                    && !inner.iter().any(|ring| ring_contains(ring, lat, lng))
+            }
+        }
+    }
+}
+
+/// point in polygon test by ray casting
+fn ring_contains(ring: &[(f32, f32)], lat: f32, lng: f32) -> bool {
+    let mut result = false;
+    let mut j = ring.len().wrapping_sub(1);
+    for i in 0..ring.len() {
+        let (lng_i, lat_i) = ring[i];
+        let (lng_j, lat_j) = ring[j];
+        if (lat_i > lat) != (lat_j > lat)
+            && lng < (lng_j - lng_i) * (lat - lat_i) / (lat_j - lat_i) + lng_i
+        {
+            result = !result;
+        }
+        j = i;
+    }
+    result
+}
+
+/// parse a bounding box given as "lng_min,lat_min,lng_max,lat_max"
+pub fn parse_bbox(s: &str) -> Result<ClipArea, String> {
+    let values = s
+        .split(',')
+        .map(|v| v.trim().parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|_| format!("invalid bounding box {}", s))?;
+    match values.as_slice() {
+        [lng_min, lat_min, lng_max, lat_max] if lng_min < lng_max && lat_min < lat_max => {
+            Ok(ClipArea::BoundingBox {
+                lat_min: *lat_min,
+                lat_max: *lat_max,
+                lng_min: *lng_min,
+                lng_max: *lng_max,
+            })
+        }
+        _ => Err(format!("invalid bounding box {}", s)),
+    }
+}
+
+/// parse a polygon in the osmosis format
+// info from: https://wiki.openstreetmap.org/wiki/Osmosis/Polygon_Filter_File_Format
+pub fn parse_poly(content: &str) -> Result<ClipArea, String> {
+    let mut outer = Vec::<Vec<(f32, f32)>>::new();
+    let mut inner = Vec::<Vec<(f32, f32)>>::new();
+    // first line is the name of the polygon
+    let mut lines = content.lines().map(|l| l.trim()).skip(1);
+    loop {
+        let section = match lines.next() {
+            Some("END") => break,
+            Some("") => continue,
+            Some(section) => section,
+            None => return Err("missing END of polygon".to_string()),
+        };
+        let mut ring = Vec::<(f32, f32)>::new();
+        loop {
+            match lines.next() {
+                Some("END") => break,
+                Some("") => continue,
+                Some(line) => {
+                    let coordinates: Vec<f32> = line
+                        .split_whitespace()
+                        .filter_map(|v| v.parse().ok())
+                        .collect();
+                    match coordinates.as_slice() {
+                        [lng, lat] => ring.push((*lng, *lat)),
+                        _ => return Err(format!("invalid coordinates {}", line)),
+                    }
+                }
+                None => return Err(format!("missing END of section {}", section)),
+            }
+        }
+        if section.starts_with('!') {
+            inner.push(ring);
+        } else {
+            outer.push(ring);
+        }
+    }
+    if outer.is_empty() {
+        return Err("polygon has no outer ring".to_string());
+    }
+    Ok(ClipArea::Polygon { outer, inner })
+}
+
+pub fn read_poly(filename: &str) -> ClipArea {
+    let path = Path::new(&filename);
+    if !path.exists() {
+        println!("{} not found", filename);
+        std::process::exit(1);
+    }
+    let content = std::fs::read_to_string(path).unwrap();
+    match parse_poly(&content) {
+        Ok(area) => area,
+        Err(e) => {
+            println!("could not parse {}: {}", filename, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// check for every node if it is inside the area
+pub fn get_inside_nodes(nodes: &[Node], area: &ClipArea) -> Vec<bool> {
+    nodes
+        .par_iter()
+        .map(|node| area.contains(node.latitude, node.longitude))
+        .collect()
+}
+
+/// remove all edges outside of the area.
+/// with complete_ways, all edges of ways having at least one node inside are kept
+pub fn clip_edges(full_edges: &mut Vec<OsmWay>, inside: &[bool], complete_ways: bool) {
+    if complete_ways {
+        let kept_ways: HashSet<WayId> = full_edges
+            .iter()
+            .filter(|edge| inside[edge.source] || inside[edge.target])
+            .map(|edge| edge.way)
+            .collect();
+        full_edges.retain(|edge| kept_ways.contains(&edge.way));
+    } else {
+        full_edges.retain(|edge| inside[edge.source] && inside[edge.target]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge(source: NodeId, target: NodeId, way: WayId) -> OsmWay {
+        OsmWay {
+            source,
+            target,
+            speed: 50,
+            distance: 0,
+            penalty: 0,
+            way,
+            ascent: 0,
+            descent: 0,
+            ferry: false,
+            delay: 0,
+            speed_factor: 100,
+            duration: None,
+        }
+    }
+
+    #[test]
+    fn bbox() {
+        let area = parse_bbox("9.0,48.0,10.0,49.5").unwrap();
+        assert!(area.contains(48.5, 9.5));
+        assert!(!area.contains(50.0, 9.5));
+        assert!(!area.contains(48.5, 8.9));
+        assert!(parse_bbox("10.0,48.0,9.0,49.5").is_err());
+        assert!(parse_bbox("9.0,48.0,10.0").is_err());
+    }
+
+    #[test]
+    fn poly() {
+        let content = "test\n\
+            1\n\
+            0.0 0.0\n\
+            10.0 0.0\n\
+            10.0 10.0\n\
+            0.0 10.0\n\
+            END\n\
+            !2\n\
+            4.0 4.0\n\
+            6.0 4.0\n\
+            6.0 6.0\n\
+            4.0 6.0\n\
+            END\n\
+            END\n";
+        let area = parse_poly(content).unwrap();
+        assert!(area.contains(2.0, 2.0));
+        assert!(!area.contains(5.0, 5.0));
+        assert!(!area.contains(11.0, 5.0));
+        assert!(parse_poly("test\n1\n0.0 0.0\n").is_err());
+    }
+
+    #[test]
+    fn clipping() {
+        let inside = vec![true, true, false, false];
+        let full_edges = vec![edge(0, 1, 1), edge(1, 2, 1), edge(2, 3, 2)];
+
+        let mut clipped = full_edges.clone();
+        clip_edges(&mut clipped, &inside, false);
+        assert_eq!(clipped, vec![edge(0, 1, 1)]);
+
+        let mut clipped = full_edges.clone();
+        clip_edges(&mut clipped, &inside, true);
+        assert_eq!(clipped, vec![edge(0, 1, 1), edge(1, 2, 1)]);
+    }
+}
@@ -0,0 +1,203 @@
+use super::*;
+
+/// get the strongly connected component of every node (tarjan, without recursion).
+/// edges have to be sorted by source, like after generating the offsets
+pub fn get_components(amount_nodes: usize, edges: &[Way], up_offset: &[EdgeId]) -> Vec<usize> {
+    let mut index = vec![INVALID_NODE; amount_nodes];
+    let mut low_link = vec![0; amount_nodes];
+    let mut on_stack = vec![false; amount_nodes];
+    let mut stack = Vec::<NodeId>::new();
+    let mut components = vec![INVALID_NODE; amount_nodes];
+    let mut amount_components = 0;
+    let mut next_index = 0;
+    // node and its next edge to visit
+    let mut call_stack = Vec::<(NodeId, EdgeId)>::new();
+
+    for start in 0..amount_nodes {
+        if index[start] != INVALID_NODE {
+            continue;
+        }
+        index[start] = next_index;
+        low_link[start] = next_index;
+        next_index += 1;
+        stack.push(start);
+        on_stack[start] = true;
+        call_stack.push((start, up_offset[start]));
+
+        while let Some(&(node, edge_id)) = call_stack.last() {
+            if edge_id < up_offset[node + 1] {
+                call_stack.last_mut().unwrap().1 += 1;
+                let target = edges[edge_id].target;
+                if index[target] == INVALID_NODE {
+                    index[target] = next_index;
+                    low_link[target] = next_index;
+                    next_index += 1;
+                    stack.push(target);
+                    on_stack[target] = true;
+                    call_stack.push((target, up_offset[target]));
+                } else if on_stack[target] {
+                    low_link[node] = low_link[node].min(index[target]);
+                }
+                continue;
+            }
+
+            call_stack.pop();
+            if let Some(&(parent, _)) = call_stack.last() {
+                low_link[parent] = low_link[parent].min(low_link[node]);
+            }
+            if low_link[node] == index[node] {
+                loop {
+                    let member = stack.pop().unwrap();
+                    on_stack[member] = false;
+                    components[member] = amount_components;
+                    if member == node {
+                        break;
+                    }
+                }
+                amount_components += 1;
+            }
+        }
+    }
+    components
+}
+
+/// nodes in the largest component or in a component with at least min_size nodes
+pub fn get_kept_nodes(components: &[usize], min_size: Option<usize>) -> Vec<bool> {
+    let amount_components = components.iter().max().map_or(0, |max| max + 1);
+    let mut sizes = vec![0; amount_components];
+    for component in components {
+        sizes[*component] += 1;
+    }
+    // the first of equal sized components is used
+    let largest = (0..amount_components).rev().max_by_key(|c| sizes[*c]);
+    components
+        .iter()
+        .map(|c| Some(*c) == largest || min_size.is_some_and(|min_size| sizes[*c] >= min_size))
+        .collect()
+}
+
+/// remove all nodes, which are not kept, and their edges. the remaining nodes are renumbered
+pub fn remove_nodes(
+    nodes: &mut Vec<Node>,
+    edges: &mut Vec<Way>,
+    original_nodes: &mut Vec<NodeId>,
+    kept: &[bool],
+) {
+    let mut new_ids = vec![INVALID_NODE; nodes.len()];
+    let kept_nodes = kept.iter().enumerate().filter(|(_, kept)| **kept);
+    for (new_id, (node, _)) in kept_nodes.enumerate() {
+        new_ids[node] = new_id;
+    }
+
+    let mut kept_iter = kept.iter();
+    nodes.retain(|_| *kept_iter.next().unwrap());
+    if !original_nodes.is_empty() {
+        let mut kept_iter = kept.iter();
+        original_nodes.retain(|_| *kept_iter.next().unwrap());
+    }
+    edges.retain(|edge| kept[edge.source] && kept[edge.target]);
+    for edge in edges.iter_mut() {
+        edge.source = new_ids[edge.source];
+        edge.target = new_ids[edge.target];
+    }
+}
+
+/// only keep the largest strongly connected component and the islands with at least min_size nodes
+pub fn restrict_to_components(
+    nodes: &mut Vec<Node>,
+    edges: &mut Vec<Way>,
+    original_nodes: &mut Vec<NodeId>,
+    min_size: Option<usize>,
+) {
+    let mut up_offset = Vec::<EdgeId>::new();
+    let mut down_offset = Vec::<EdgeId>::new();
+    offset::generate_offsets(edges, &mut up_offset, &mut down_offset, nodes.len());
+
+    let components = get_components(nodes.len(), edges, &up_offset);
+    let kept = get_kept_nodes(&components, min_size);
+    let amount_removed = kept.iter().filter(|kept| !**kept).count();
+    if amount_removed > 0 {
+        remove_nodes(nodes, edges, original_nodes, &kept);
+    }
+    println!("removed #nodes outside of components: {:?}", amount_removed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node() -> Node {
+        Node {
+            latitude: 0.0,
+            longitude: 0.0,
+            rank: INVALID_RANK,
+        }
+    }
+
+    #[test]
+    fn components() {
+        // 0 <-> 1 <-> 2 -> 3 <-> 4     5 <-> 6
+        let mut edges = vec![
+            Way::new(0, 1, 1),
+            Way::new(1, 0, 1),
+            Way::new(1, 2, 1),
+            Way::new(2, 1, 1),
+            Way::new(2, 3, 1),
+            Way::new(3, 4, 1),
+            Way::new(4, 3, 1),
+            Way::new(5, 6, 1),
+            Way::new(6, 5, 1),
+        ];
+        let mut up_offset = Vec::<EdgeId>::new();
+        let mut down_offset = Vec::<EdgeId>::new();
+        offset::generate_offsets(&mut edges, &mut up_offset, &mut down_offset, 7);
+
+        let components = get_components(7, &edges, &up_offset);
+        assert_eq!(components[0], components[1]);
+        assert_eq!(components[0], components[2]);
+        assert_eq!(components[3], components[4]);
+        assert_eq!(components[5], components[6]);
+        assert_ne!(components[0], components[3]);
+        assert_ne!(components[0], components[5]);
+        assert_ne!(components[3], components[5]);
+
+        let kept = get_kept_nodes(&components, None);
+        assert_eq!(kept, vec![true, true, true, false, false, false, false]);
+        let kept = get_kept_nodes(&components, Some(2));
+        assert_eq!(kept, vec![true; 7]);
+    }
+
+    #[test]
+    fn restrict() {
+        // 0 <-> 1 <-> 2     3 -> 4
+        let mut nodes = vec![node(); 5];
+        let mut edges = vec![
+            Way::new(0, 1, 1),
+            Way::new(1, 0, 1),
+            Way::new(1, 2, 1),
+            Way::new(2, 1, 1),
+            Way::new(3, 4, 1),
+        ];
+        let mut original_nodes = vec![10, 11, 12, 13, 14];
+        restrict_to_components(&mut nodes, &mut edges, &mut original_nodes, None);
+        assert_eq!(nodes.len(), 3);
+        assert_eq!(edges.len(), 4);
+        assert_eq!(original_nodes, vec![10, 11, 12]);
+
+        // islands of the given size are kept
+        let mut nodes = vec![node(); 5];
+        let mut edges = vec![
+            Way::new(2, 1, 1),
+            Way::new(1, 2, 1),
+            Way::new(3, 0, 1),
+            Way::new(0, 3, 1),
+            Way::new(0, 4, 1),
+            Way::new(4, 0, 1),
+        ];
+        restrict_to_components(&mut nodes, &mut edges, &mut Vec::new(), Some(2));
+        assert_eq!(nodes.len(), 5);
+        restrict_to_components(&mut nodes, &mut edges, &mut Vec::new(), None);
+        assert_eq!(nodes.len(), 3);
+        assert!(edges.iter().all(|e| e.source < 3 && e.target < 3));
+    }
+}
@@ -3,14 +3,29 @@ pub type EdgeId = usize;
 pub type Weight = usize;
 pub type Rank = usize;
 pub type GridId = usize;
+pub type WayId = usize;
 
 pub const INVALID_NODE: NodeId = std::usize::MAX;
 pub const INVALID_EDGE: NodeId = std::usize::MAX;
+pub const INVALID_WAY: WayId = usize::MAX;
 pub const WEIGHT_MAX: Weight = std::usize::MAX;
 pub const INVALID_RANK: Rank = std::usize::MAX;
 
+// additional costs in percent for ways only usable to reach a destination
+pub const DESTINATION_PENALTY: usize = 100;
+
+// amount of pbf-blobs decoded at once per thread
+pub const BLOBS_PER_THREAD: usize = 4;
+
 pub const DIST_MULTIPLICATOR: usize = 262144; // 2^18
 
 // ratio: north south 876km / west east 640 km ~ 100:136
 pub const LAT_GRID_AMOUNT: usize = 136;
 pub const LNG_GRID_AMOUNT: usize = 100;
+
+// with more remaining nodes, the independent set is only taken from the quarter
+// of the nodes with the lowest heuristic
+pub const MIN_SORTED_NODES: usize = 10_000;
+
+// minimum time between two checkpoints of the contraction
+pub const CHECKPOINT_SECONDS: u64 = 300;
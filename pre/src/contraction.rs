@@ -1,5 +1,87 @@
 use super::*;
+use bincode::{deserialize_from, serialize_into};
+use serde::{Deserialize, Serialize};
 use std::cmp::Reverse;
+use std::collections::hash_map::DefaultHasher;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, BufWriter};
+use std::time::Duration;
+
+/// where and how often the state of the contraction is saved
+pub struct CheckpointSettings {
+    pub filename: String,
+    /// minimum time between two checkpoints, they are written after completed levels
+    pub interval: Duration,
+}
+
+/// state of the contraction after a completed level.
+/// the ids of the edges are not serialized with them and stored separately
+#[derive(Serialize)]
+struct CheckpointRef<'a> {
+    fingerprint: u64,
+    rank: Rank,
+    remaining_nodes: &'a BTreeSet<NodeId>,
+    ranks: Vec<Rank>,
+    edges: &'a [Way],
+    edge_ids: Vec<Option<EdgeId>>,
+    resulting_edges: &'a [Way],
+    resulting_edge_ids: Vec<Option<EdgeId>>,
+    heuristics: Vec<isize>,
+    deleted_neighbors: &'a [usize],
+    shortcut_id: usize,
+}
+
+/// owned version of CheckpointRef, for reading
+#[derive(Deserialize)]
+struct Checkpoint {
+    fingerprint: u64,
+    rank: Rank,
+    remaining_nodes: BTreeSet<NodeId>,
+    ranks: Vec<Rank>,
+    edges: Vec<Way>,
+    edge_ids: Vec<Option<EdgeId>>,
+    resulting_edges: Vec<Way>,
+    resulting_edge_ids: Vec<Option<EdgeId>>,
+    heuristics: Vec<isize>,
+    deleted_neighbors: Vec<usize>,
+    shortcut_id: usize,
+}
+
+/// identifies the graph before the contraction, so that no checkpoint of another graph is used
+fn get_fingerprint(nodes: &[Node], edges: &[Way]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    nodes.len().hash(&mut hasher);
+    for edge in edges {
+        (edge.source, edge.target, edge.weight).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// write to a temporary file first, an interrupted write keeps the previous checkpoint
+fn write_checkpoint(filename: &str, checkpoint: &CheckpointRef) {
+    let temporary = format!("{}.tmp", filename);
+    let mut writer = BufWriter::new(File::create(&temporary).unwrap());
+    serialize_into(&mut writer, checkpoint).unwrap();
+    drop(writer);
+    std::fs::rename(&temporary, filename).unwrap();
+}
+
+/// checkpoint of the same graph, if there is one
+fn read_checkpoint(filename: &str, fingerprint: u64) -> Option<Checkpoint> {
+    let file = File::open(filename).ok()?;
+    let checkpoint: Checkpoint = deserialize_from(&mut BufReader::new(file)).ok()?;
+    match checkpoint.fingerprint == fingerprint {
+        true => Some(checkpoint),
+        false => None,
+    }
+}
+
+fn set_ids(edges: &mut [Way], ids: &[Option<EdgeId>]) {
+    for (edge, id) in edges.iter_mut().zip(ids) {
+        edge.id = *id;
+    }
+}
 
 /// return new generated shortcuts
 #[allow(clippy::too_many_arguments)]
@@ -176,15 +258,21 @@ fn revert_indices(edges: &mut Vec<Way>) {
     });
 }
 
-/// run full contraction
+/// run full contraction.
+/// with checkpoints, a checkpoint of the same graph is resumed and the state is saved
+/// regularly. the last checkpoint is kept and has to be removed by the caller
 pub fn run_contraction(
     nodes: &mut Vec<Node>,
     edges: &mut Vec<Way>,
     up_offset: &mut Vec<EdgeId>,
     down_offset: &mut Vec<EdgeId>,
     down_index: &mut Vec<EdgeId>,
+    checkpoints: Option<&CheckpointSettings>,
+    min_sorted_nodes: usize,
 ) {
     let amount_nodes: usize = nodes.len();
+    let fingerprint = get_fingerprint(nodes, edges);
+    let mut checkpoint_time = Instant::now();
     // for keeping track of new created edge_ids
     let shortcut_id = AtomicUsize::new(edges.len());
 
@@ -203,26 +291,47 @@ pub fn run_contraction(
 
     let mut rank: Rank = 0;
     let mut minimas_bool = VisitedList::new(amount_nodes);
-
-    // update priorities of all nodes with simulated contractions
     let mut deleted_neighbors = vec![0; amount_nodes];
-    let mut heuristics = ordering::calculate_heuristics(
-        &deleted_neighbors,
-        &shortcut_id,
-        rank,
-        amount_nodes,
-        edges,
-        up_offset,
-        down_offset,
-        down_index,
-    );
 
-    let thread_count = num_cpus::get();
+    let checkpoint = checkpoints.and_then(|c| read_checkpoint(&c.filename, fingerprint));
+    let mut heuristics = match checkpoint {
+        Some(checkpoint) => {
+            println!("resuming the contraction at rank {}", checkpoint.rank);
+            rank = checkpoint.rank;
+            remaining_nodes = checkpoint.remaining_nodes;
+            for (node, node_rank) in nodes.iter_mut().zip(checkpoint.ranks) {
+                node.rank = node_rank;
+            }
+            *edges = checkpoint.edges;
+            set_ids(edges, &checkpoint.edge_ids);
+            resulting_edges = checkpoint.resulting_edges;
+            set_ids(&mut resulting_edges, &checkpoint.resulting_edge_ids);
+            deleted_neighbors = checkpoint.deleted_neighbors;
+            shortcut_id.store(checkpoint.shortcut_id, Ordering::SeqCst);
+            *down_index = offset::generate_offsets(edges, up_offset, down_offset, amount_nodes);
+            (checkpoint.heuristics.into_iter())
+                .map(AtomicIsize::new)
+                .collect()
+        }
+        // update priorities of all nodes with simulated contractions
+        None => ordering::calculate_heuristics(
+            &deleted_neighbors,
+            &shortcut_id,
+            rank,
+            amount_nodes,
+            edges,
+            up_offset,
+            down_offset,
+            down_index,
+        ),
+    };
+
+    let thread_count = rayon::current_num_threads();
 
     while !remaining_nodes.is_empty() {
         let get_independent_set_time = Instant::now();
         // I ← independent node set
-        let mut minimas = ordering::get_independent_set(
+        let minimas = ordering::get_independent_set(
             &remaining_nodes,
             &heuristics,
             &mut minimas_bool,
@@ -230,6 +339,7 @@ pub fn run_contraction(
             up_offset,
             down_offset,
             down_index,
+            min_sorted_nodes,
         );
         if remaining_nodes.len() > 100_000 {
             println!(
@@ -239,35 +349,31 @@ pub fn run_contraction(
         }
 
         let other_time = Instant::now();
-        // E ← necessary shortcuts
-        let parallel_shortcuts: RwLock<Vec<Way>> =
-            RwLock::new(Vec::with_capacity(2 * minimas.len()));
-
+        // E ← necessary shortcuts, collected in the order of the nodes.
+        // they get their ids after sorting, so the result does not depend on the threads
+        let unsorted_id = AtomicUsize::new(0);
         let chunk_size = (minimas.len() + thread_count - 1) / thread_count;
-        if chunk_size > 0 {
-            rayon::scope(|s| {
-                for datachunk_items in minimas.chunks_mut(chunk_size) {
-                    s.spawn(|_| {
-                        let mut dijkstra = dijkstra::Dijkstra::new(amount_nodes);
-                        for node in datachunk_items {
-                            let node_shortcuts = calc_shortcuts(
-                                *node,
-                                &mut dijkstra,
-                                edges,
-                                up_offset,
-                                down_offset,
-                                down_index,
-                                &shortcut_id,
-                                rank,
-                            );
-                            let mut tmp = parallel_shortcuts.write().unwrap();
-                            tmp.extend(node_shortcuts);
-                        }
-                    });
+        let mut shortcuts: Vec<Way> = minimas
+            .par_chunks(chunk_size.max(1))
+            .map(|datachunk_items| {
+                let mut dijkstra = dijkstra::Dijkstra::new(amount_nodes);
+                let mut chunk_shortcuts = Vec::<Way>::new();
+                for node in datachunk_items {
+                    chunk_shortcuts.extend(calc_shortcuts(
+                        *node,
+                        &mut dijkstra,
+                        edges,
+                        up_offset,
+                        down_offset,
+                        down_index,
+                        &unsorted_id,
+                        rank,
+                    ));
                 }
-            });
-        }
-        let mut shortcuts = parallel_shortcuts.into_inner().unwrap();
+                chunk_shortcuts
+            })
+            .flatten()
+            .collect();
 
         // collecting all edges to be removed
         let mut connected_edges: Vec<EdgeId> = minimas
@@ -279,6 +385,9 @@ pub fn run_contraction(
         // dedup shortcuts with same start, end but have to keep with best weight preventing shortcuts in diamond-shapes
         shortcuts.par_sort_unstable();
         shortcuts.dedup_by(|a, b| a.source == b.source && a.target == b.target);
+        for shortcut in shortcuts.iter_mut() {
+            shortcut.id = Some(shortcut_id.fetch_add(1, Ordering::SeqCst));
+        }
 
         // update heuristic of neighbors of I with simulated contractions
         let mut neighbors: Vec<NodeId> = minimas
@@ -338,6 +447,33 @@ pub fn run_contraction(
             connected_edges.len(),
             resulting_edges.len()
         );
+
+        if let Some(checkpoints) = checkpoints {
+            if !remaining_nodes.is_empty() && checkpoint_time.elapsed() >= checkpoints.interval {
+                let checkpoint_write_time = Instant::now();
+                let get_ids = |edges: &[Way]| edges.iter().map(|edge| edge.id).collect();
+                write_checkpoint(
+                    &checkpoints.filename,
+                    &CheckpointRef {
+                        fingerprint,
+                        rank,
+                        remaining_nodes: &remaining_nodes,
+                        ranks: nodes.iter().map(|node| node.rank).collect(),
+                        edges,
+                        edge_ids: get_ids(edges),
+                        resulting_edges: &resulting_edges,
+                        resulting_edge_ids: get_ids(&resulting_edges),
+                        heuristics: (heuristics.iter())
+                            .map(|h| h.load(Ordering::Relaxed))
+                            .collect(),
+                        deleted_neighbors: &deleted_neighbors,
+                        shortcut_id: shortcut_id.load(Ordering::SeqCst),
+                    },
+                );
+                println!("Checkpoint in: {:?}", checkpoint_write_time.elapsed());
+                checkpoint_time = Instant::now();
+            }
+        }
     }
     println!("max_rank: {:?}", rank);
 
@@ -1076,4 +1212,127 @@ mod tests {
 
         assert_eq!(edges, expected_edges);
     }
+
+    /// bidirectional grid of size x size nodes with varying weights
+    fn grid_graph(size: usize) -> (Vec<Node>, Vec<Way>) {
+        let nodes = (0..size * size)
+            .map(|node| Node {
+                latitude: (node / size) as f32,
+                longitude: (node % size) as f32,
+                rank: INVALID_RANK,
+            })
+            .collect();
+        let mut edges = Vec::<Way>::new();
+        for node in 0..size * size {
+            let mut neighbors = Vec::<NodeId>::new();
+            if node % size + 1 < size {
+                neighbors.push(node + 1);
+            }
+            if node + size < size * size {
+                neighbors.push(node + size);
+            }
+            for neighbor in neighbors {
+                let weight = 1 + (node * 7 + neighbor * 3) % 5;
+                edges.push(Way::new(node, neighbor, weight));
+                edges.push(Way::new(neighbor, node, weight));
+            }
+        }
+        (nodes, edges)
+    }
+
+    /// ranks and (source, target, weight) of all edges after the contraction
+    fn contract(
+        mut nodes: Vec<Node>,
+        mut edges: Vec<Way>,
+        checkpoints: Option<&CheckpointSettings>,
+    ) -> (Vec<Rank>, Vec<(NodeId, NodeId, Weight)>) {
+        let mut up_offset = Vec::<EdgeId>::new();
+        let mut down_offset = Vec::<EdgeId>::new();
+        let mut down_index =
+            offset::generate_offsets(&mut edges, &mut up_offset, &mut down_offset, nodes.len());
+        run_contraction(
+            &mut nodes,
+            &mut edges,
+            &mut up_offset,
+            &mut down_offset,
+            &mut down_index,
+            checkpoints,
+            MIN_SORTED_NODES,
+        );
+        let mut result: Vec<(NodeId, NodeId, Weight)> = (edges.iter())
+            .map(|edge| (edge.source, edge.target, edge.weight))
+            .collect();
+        result.sort_unstable();
+        (nodes.iter().map(|node| node.rank).collect(), result)
+    }
+
+    #[test]
+    fn resume_contraction() {
+        let filename = std::env::temp_dir().join("osm_ch_pre-resume_contraction.checkpoint");
+        let checkpoints = CheckpointSettings {
+            filename: filename.to_str().unwrap().to_string(),
+            interval: Duration::from_secs(0),
+        };
+        let (nodes, edges) = grid_graph(6);
+        let expected = contract(nodes.clone(), edges.clone(), None);
+
+        // a checkpoint is written after every level, the last one is kept
+        let contracted = contract(nodes.clone(), edges.clone(), Some(&checkpoints));
+        assert_eq!(contracted, expected);
+        let mut sorted_edges = edges.clone();
+        let mut offsets = (Vec::<EdgeId>::new(), Vec::<EdgeId>::new());
+        offset::generate_offsets(
+            &mut sorted_edges,
+            &mut offsets.0,
+            &mut offsets.1,
+            nodes.len(),
+        );
+        let fingerprint = get_fingerprint(&nodes, &sorted_edges);
+        let checkpoint = read_checkpoint(&checkpoints.filename, fingerprint).unwrap();
+        assert!(checkpoint.rank > 0 && !checkpoint.remaining_nodes.is_empty());
+        assert!(read_checkpoint(&checkpoints.filename, fingerprint + 1).is_none());
+
+        // the contraction continues after the last completed level
+        let resumed = contract(nodes, edges, Some(&checkpoints));
+        assert_eq!(resumed, expected);
+        std::fs::remove_file(&checkpoints.filename).unwrap();
+    }
+
+    #[test]
+    fn thread_independent_contraction() {
+        // serialized ranks, edges and their ids after the contraction.
+        // the first levels sort the remaining nodes by their heuristic
+        let serialized_contraction = |threads: usize| {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            pool.install(|| {
+                let (mut nodes, mut edges) = grid_graph(12);
+                let mut up_offset = Vec::<EdgeId>::new();
+                let mut down_offset = Vec::<EdgeId>::new();
+                let mut down_index = offset::generate_offsets(
+                    &mut edges,
+                    &mut up_offset,
+                    &mut down_offset,
+                    nodes.len(),
+                );
+                run_contraction(
+                    &mut nodes,
+                    &mut edges,
+                    &mut up_offset,
+                    &mut down_offset,
+                    &mut down_index,
+                    None,
+                    50,
+                );
+                let ids: Vec<Option<EdgeId>> = edges.iter().map(|edge| edge.id).collect();
+                bincode::serialize(&(nodes, edges, ids, up_offset, down_offset, down_index))
+                    .unwrap()
+            })
+        };
+        let expected = serialized_contraction(1);
+        assert_eq!(serialized_contraction(4), expected);
+        assert_eq!(serialized_contraction(4), expected);
+    }
 }
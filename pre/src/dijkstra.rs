@@ -350,7 +350,7 @@ mod tests {
         assert!(result.is_some());
         let path = result.unwrap();
         assert_eq!(path.0.len(), 0);
-        assert_eq!(path.0, []);
+        assert_eq!(path.0, Vec::<EdgeId>::new());
         assert_eq!(path.1, 0);
 
         let result = d.find_path(6, 3, &up_offset, &edges, true, 0);
@@ -0,0 +1,176 @@
+use super::*;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+// info from: http://www.diag.uniroma1.it/challenge9/format.shtml
+
+/// files ending with .gr are read as DIMACS graph with the coordinates in the .co-file
+pub fn is_dimacs(filename: &str) -> bool {
+    filename.ends_with(".gr")
+}
+
+/// coordinates next to the graph, e.g. USA-road-t.NY.gr and USA-road-t.NY.co
+fn get_coordinates_filename(filename: &str) -> String {
+    format!("{}.co", filename.trim_end_matches(".gr"))
+}
+
+/// name of the output file, e.g. `USA-road-t.NY.gr` becomes `USA-road-t.NY.fmi`
+pub fn get_output_filename(filename: &str) -> String {
+    format!("{}.fmi", filename.trim_end_matches(".gr"))
+}
+
+/// values of a line after its type, e.g. `a 1 2 803` for an arc
+fn parse_values(line: &str, amount: usize) -> Result<Vec<i64>, String> {
+    let values = line
+        .split_whitespace()
+        .skip(1)
+        .map(|v| v.parse::<i64>())
+        .collect::<Result<Vec<i64>, _>>()
+        .map_err(|_| format!("invalid line {}", line))?;
+    match values.len() == amount {
+        true => Ok(values),
+        false => Err(format!("invalid line {}", line)),
+    }
+}
+
+/// convert an id starting at 1 to an own id
+fn get_node_id(id: i64, amount_nodes: usize) -> Result<NodeId, String> {
+    match id >= 1 && id as usize <= amount_nodes {
+        true => Ok(id as NodeId - 1),
+        false => Err(format!("unknown node {}", id)),
+    }
+}
+
+/// parse the amount of nodes and the arcs of a graph, loops are skipped
+pub fn parse_graph<R: BufRead>(reader: R) -> Result<(usize, Vec<Way>), String> {
+    let mut amount_nodes: Option<usize> = None;
+    let mut edges = Vec::<Way>::new();
+    for line in reader.lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        match (line.chars().next(), amount_nodes) {
+            (Some('p'), None) => {
+                // p sp n m
+                let values: Vec<&str> = line.split_whitespace().collect();
+                match values.as_slice() {
+                    ["p", "sp", n, m] => {
+                        amount_nodes = Some(n.parse().map_err(|_| format!("invalid {}", line))?);
+                        edges.reserve(m.parse().unwrap_or(0));
+                    }
+                    _ => return Err(format!("invalid problem line {}", line)),
+                }
+            }
+            (Some('a'), Some(amount_nodes)) => {
+                let values = parse_values(&line, 3)?;
+                let source = get_node_id(values[0], amount_nodes)?;
+                let target = get_node_id(values[1], amount_nodes)?;
+                if values[2] < 0 {
+                    return Err(format!("negative weight {}", line));
+                }
+                if source != target {
+                    edges.push(Way::new(source, target, values[2] as Weight));
+                }
+            }
+            (Some('a'), None) => return Err("arc before the problem line".to_string()),
+            _ => (),
+        }
+    }
+    match amount_nodes {
+        Some(amount_nodes) => Ok((amount_nodes, edges)),
+        None => Err("missing problem line".to_string()),
+    }
+}
+
+/// parse the coordinates in millionths of degrees of all nodes
+pub fn parse_coordinates<R: BufRead>(reader: R, amount_nodes: usize) -> Result<Vec<Node>, String> {
+    let mut coordinates: Vec<Option<(f32, f32)>> = vec![None; amount_nodes];
+    for line in reader.lines() {
+        let line = line.map_err(|e| e.to_string())?;
+        if line.starts_with('v') {
+            // v id longitude latitude
+            let values = parse_values(&line, 3)?;
+            let node = get_node_id(values[0], amount_nodes)?;
+            let (longitude, latitude) = (values[1] as f64 / 1e6, values[2] as f64 / 1e6);
+            coordinates[node] = Some((latitude as f32, longitude as f32));
+        }
+    }
+    coordinates
+        .iter()
+        .enumerate()
+        .map(|(node, coordinate)| match coordinate {
+            Some((latitude, longitude)) => Ok(Node {
+                latitude: *latitude,
+                longitude: *longitude,
+                rank: INVALID_RANK,
+            }),
+            None => Err(format!("missing coordinates of node {}", node + 1)),
+        })
+        .collect()
+}
+
+fn open(filename: &str) -> BufReader<File> {
+    let path = Path::new(&filename);
+    if !path.exists() {
+        println!("{} not found", filename);
+        std::process::exit(1);
+    }
+    BufReader::new(File::open(path).unwrap())
+}
+
+/// read the nodes and edges of a DIMACS graph, the weights are used as they are
+pub fn read_dimacs(filename: &str) -> (Vec<Node>, Vec<Way>) {
+    let coordinates_filename = get_coordinates_filename(filename);
+    let result = parse_graph(open(filename))
+        .map_err(|e| (filename, e))
+        .and_then(|(amount_nodes, edges)| {
+            parse_coordinates(open(&coordinates_filename), amount_nodes)
+                .map(|nodes| (nodes, edges))
+                .map_err(|e| (coordinates_filename.as_str(), e))
+        });
+    match result {
+        Ok(graph) => graph,
+        Err((filename, e)) => {
+            println!("could not parse {}: {}", filename, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dimacs() {
+        let graph = "c 9th DIMACS Implementation Challenge\n\
+            p sp 3 4\n\
+            a 1 2 803\n\
+            a 2 1 803\n\
+            a 2 3 158\n\
+            a 3 3 10\n";
+        let (amount_nodes, edges) = parse_graph(graph.as_bytes()).unwrap();
+        assert_eq!(amount_nodes, 3);
+        assert_eq!(
+            edges,
+            vec![
+                Way::new(0, 1, 803),
+                Way::new(1, 0, 803),
+                Way::new(1, 2, 158)
+            ]
+        );
+        assert!(parse_graph("p sp 2 1\na 1 3 5\n".as_bytes()).is_err());
+        assert!(parse_graph("a 1 2 5\n".as_bytes()).is_err());
+
+        let coordinates = "p aux sp co 3\n\
+            v 1 -73530767 41085396\n\
+            v 3 -73530538 41086098\n\
+            v 2 -73519366 41048796\n";
+        let nodes = parse_coordinates(coordinates.as_bytes(), 3).unwrap();
+        assert_eq!(
+            (nodes[0].latitude, nodes[0].longitude),
+            (41.085396, -73.53077)
+        );
+        assert_eq!(nodes[2].latitude, 41.086098);
+        assert!(parse_coordinates("v 1 0 0\n".as_bytes(), 2).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use super::*;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// value of missing measurements
+const SRTM_VOID: i16 = -32768;
+
+/// one SRTM-tile covering one degree of latitude and longitude
+struct Tile {
+    /// amount of rows and columns (1201 for SRTM3, 3601 for SRTM1)
+    size: usize,
+    /// heights in meters, rows from north to south
+    heights: Vec<i16>,
+}
+
+impl Tile {
+    fn get_height(&self, row: usize, column: usize) -> Option<f32> {
+        match self.heights[row * self.size + column] {
+            SRTM_VOID => None,
+            height => Some(height as f32),
+        }
+    }
+
+    /// bilinear interpolation inside of the tile, missing measurements are skipped
+    fn get_elevation(&self, lat: f32, lng: f32, tile_lat: i32, tile_lng: i32) -> Option<f32> {
+        let max = (self.size - 1) as f32;
+        let y = ((tile_lat + 1) as f32 - lat).clamp(0.0, 1.0) * max;
+        let x = (lng - tile_lng as f32).clamp(0.0, 1.0) * max;
+        let (row, column) = (y.floor() as usize, x.floor() as usize);
+        let (next_row, next_column) = (
+            (row + 1).min(self.size - 1),
+            (column + 1).min(self.size - 1),
+        );
+        let (dy, dx) = (y - row as f32, x - column as f32);
+
+        let mut sum = 0.0;
+        let mut weights = 0.0;
+        for (r, c, weight) in [
+            (row, column, (1.0 - dy) * (1.0 - dx)),
+            (row, next_column, (1.0 - dy) * dx),
+            (next_row, column, dy * (1.0 - dx)),
+            (next_row, next_column, dy * dx),
+        ] {
+            if let Some(height) = self.get_height(r, c) {
+                sum += height * weight;
+                weights += weight;
+            }
+        }
+        if weights > 0.0 {
+            Some(sum / weights)
+        } else {
+            None
+        }
+    }
+}
+
+/// elevation data of SRTM .hgt-files from a directory
+pub struct Srtm {
+    tiles: HashMap<(i32, i32), Tile>,
+}
+
+/// south-west corner of the tile containing the coordinate
+fn get_tile_id(lat: f32, lng: f32) -> (i32, i32) {
+    (lat.floor() as i32, lng.floor() as i32)
+}
+
+/// name of the tile, e.g. N48E009.hgt
+fn get_tile_name((lat, lng): (i32, i32)) -> String {
+    format!(
+        "{}{:02}{}{:03}.hgt",
+        if lat >= 0 { 'N' } else { 'S' },
+        lat.abs(),
+        if lng >= 0 { 'E' } else { 'W' },
+        lng.abs()
+    )
+}
+
+fn read_tile(filename: &Path) -> Option<Tile> {
+    let bytes = std::fs::read(filename).ok()?;
+    let size = ((bytes.len() / 2) as f64).sqrt() as usize;
+    if size < 2 || size * size * 2 != bytes.len() {
+        println!("invalid size of {}", filename.display());
+        return None;
+    }
+    let heights = bytes
+        .chunks_exact(2)
+        .map(|pair| i16::from_be_bytes([pair[0], pair[1]]))
+        .collect();
+    Some(Tile { size, heights })
+}
+
+impl Srtm {
+    /// load all tiles needed for the nodes, missing tiles are skipped
+    pub fn load(directory: &str, nodes: &[Node]) -> Self {
+        if !Path::new(directory).is_dir() {
+            println!("{} not found", directory);
+            std::process::exit(1);
+        }
+        let tile_ids: HashSet<(i32, i32)> = nodes
+            .iter()
+            .map(|node| get_tile_id(node.latitude, node.longitude))
+            .collect();
+        let tiles: HashMap<(i32, i32), Tile> = tile_ids
+            .into_par_iter()
+            .filter_map(|id| {
+                let filename = Path::new(directory).join(get_tile_name(id));
+                read_tile(&filename).map(|tile| (id, tile))
+            })
+            .collect();
+        println!("elevation tiles: {:?}", tiles.len());
+        Srtm { tiles }
+    }
+
+    /// elevation in meters (None without data)
+    pub fn get_elevation(&self, lat: f32, lng: f32) -> Option<f32> {
+        let (tile_lat, tile_lng) = get_tile_id(lat, lng);
+        let tile = self.tiles.get(&(tile_lat, tile_lng))?;
+        tile.get_elevation(lat, lng, tile_lat, tile_lng)
+    }
+
+    pub fn get_elevations(&self, nodes: &[Node]) -> Vec<Option<f32>> {
+        nodes
+            .par_iter()
+            .map(|node| self.get_elevation(node.latitude, node.longitude))
+            .collect()
+    }
+}
+
+/// ascent and descent of every edge in decimeters
+pub fn calc_edge_climbs(full_edges: &mut Vec<OsmWay>, elevations: &[Option<f32>]) {
+    full_edges.par_iter_mut().for_each(|edge| {
+        if let (Some(source), Some(target)) = (elevations[edge.source], elevations[edge.target]) {
+            let difference = ((target - source) * 10.0).round() as isize;
+            edge.ascent = difference.max(0) as usize;
+            edge.descent = (-difference).max(0) as usize;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_names() {
+        assert_eq!(get_tile_name(get_tile_id(48.7, 9.1)), "N48E009.hgt");
+        assert_eq!(get_tile_name(get_tile_id(-33.9, -70.6)), "S34W071.hgt");
+    }
+
+    #[test]
+    fn interpolation() {
+        // 3x3 tile from north to south
+        let tile = Tile {
+            size: 3,
+            heights: vec![100, 200, 300, 0, 100, 200, SRTM_VOID, 0, 100],
+        };
+        assert_eq!(tile.get_elevation(49.0, 9.0, 48, 9), Some(100.0));
+        assert_eq!(tile.get_elevation(48.5, 9.5, 48, 9), Some(100.0));
+        assert_eq!(tile.get_elevation(48.75, 9.25, 48, 9), Some(100.0));
+        assert_eq!(tile.get_elevation(49.0, 9.25, 48, 9), Some(150.0));
+        // missing measurements are skipped
+        assert_eq!(tile.get_elevation(48.0, 9.0, 48, 9), None);
+        assert_eq!(tile.get_elevation(48.0, 9.25, 48, 9), Some(0.0));
+    }
+}
@@ -0,0 +1,473 @@
+use super::*;
+use bincode::deserialize_from;
+use serde_json::json;
+use std::fs::File;
+use std::io::{BufReader, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// file format of an export
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    /// one FeatureCollection with points for the nodes and lines for the edges
+    GeoJson,
+    /// one file for the nodes and one for the edges, the geometry as WKT
+    Csv,
+    GraphMl,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "geojson" => Ok(ExportFormat::GeoJson),
+            "csv" => Ok(ExportFormat::Csv),
+            "graphml" => Ok(ExportFormat::GraphMl),
+            _ => Err(format!("unknown export format {}", s)),
+        }
+    }
+}
+
+pub struct ExportArguments {
+    pub filename: String,
+    pub formats: Vec<ExportFormat>,
+    /// only nodes inside and edges with both nodes inside are exported
+    pub bbox: Option<clip::ClipArea>,
+    /// export the shortcuts besides the original edges
+    pub shortcuts: bool,
+}
+
+/// attributes of an exported edge, the strings are empty for shortcuts
+struct EdgeAttributes<'a> {
+    /// rank of the node bypassed by a shortcut
+    level: Option<Rank>,
+    name: &'a str,
+    reference: &'a str,
+    highway: &'a str,
+}
+
+fn get_attributes(graph: &FmiFile, edge: EdgeId) -> EdgeAttributes<'_> {
+    let way = graph.edges[edge];
+    let level = way
+        .contrated_previous
+        .map(|previous| graph.nodes[graph.edges[previous].target].rank);
+    let way_info = match graph.edge_ways.get(edge) {
+        Some(way) if *way != INVALID_WAY => graph.ways.get(*way),
+        _ => None,
+    };
+    let get_string = |id: usize| graph.strings.get(id).map_or("", |s| s.as_str());
+    EdgeAttributes {
+        level,
+        name: way_info.map_or("", |w| get_string(w.name)),
+        reference: way_info.map_or("", |w| get_string(w.reference)),
+        highway: way_info.map_or("", |w| get_string(w.highway)),
+    }
+}
+
+/// nodes inside of the bounding box and the edges between them
+fn select(
+    graph: &FmiFile,
+    bbox: Option<&clip::ClipArea>,
+    shortcuts: bool,
+) -> (Vec<NodeId>, Vec<EdgeId>) {
+    let inside: Vec<bool> = match bbox {
+        Some(bbox) => clip::get_inside_nodes(&graph.nodes, bbox),
+        None => vec![true; graph.nodes.len()],
+    };
+    let nodes = (0..graph.nodes.len()).filter(|n| inside[*n]).collect();
+    let edges = (0..graph.edges.len())
+        .filter(|e| {
+            let edge = &graph.edges[*e];
+            inside[edge.source]
+                && inside[edge.target]
+                && (shortcuts || edge.contrated_previous.is_none())
+        })
+        .collect();
+    (nodes, edges)
+}
+
+/// coordinates (latitude, longitude) of an edge including its nodes, shortcuts are unpacked
+fn get_coordinates(graph: &FmiFile, edge: EdgeId) -> Vec<(f32, f32)> {
+    let get_node = |node: NodeId| (graph.nodes[node].latitude, graph.nodes[node].longitude);
+    let mut coordinates = vec![get_node(graph.edges[edge].source)];
+    let mut stack = vec![edge];
+    while let Some(current) = stack.pop() {
+        let way = &graph.edges[current];
+        match (way.contrated_previous, way.contrated_next) {
+            (Some(previous), Some(next)) => {
+                stack.push(next);
+                stack.push(previous);
+            }
+            _ => {
+                coordinates.extend_from_slice(graph.geometry.get(current));
+                coordinates.push(get_node(way.target));
+            }
+        }
+    }
+    coordinates
+}
+
+/// coordinate for json, without the noise of converting f32 to f64
+fn to_json_degrees(value: f32) -> f64 {
+    value.to_string().parse().unwrap()
+}
+
+fn to_wkt(coordinates: &[(f32, f32)]) -> String {
+    let points: Vec<String> = coordinates
+        .iter()
+        .map(|(lat, lng)| format!("{} {}", lng, lat))
+        .collect();
+    match points.len() {
+        1 => format!("POINT ({})", points[0]),
+        _ => format!("LINESTRING ({})", points.join(", ")),
+    }
+}
+
+fn escape_csv(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn write_geojson<W: Write>(
+    writer: &mut W,
+    graph: &FmiFile,
+    nodes: &[NodeId],
+    edges: &[EdgeId],
+) -> std::io::Result<()> {
+    write!(writer, "{{\"type\":\"FeatureCollection\",\"features\":[")?;
+    let node_features = nodes.iter().map(|node| {
+        json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "Point",
+                "coordinates": [
+                    to_json_degrees(graph.nodes[*node].longitude),
+                    to_json_degrees(graph.nodes[*node].latitude),
+                ],
+            },
+            "properties": { "id": node, "rank": graph.nodes[*node].rank },
+        })
+    });
+    let edge_features = edges.iter().map(|edge| {
+        let way = &graph.edges[*edge];
+        let attributes = get_attributes(graph, *edge);
+        let coordinates: Vec<[f64; 2]> = get_coordinates(graph, *edge)
+            .iter()
+            .map(|(lat, lng)| [to_json_degrees(*lng), to_json_degrees(*lat)])
+            .collect();
+        json!({
+            "type": "Feature",
+            "geometry": { "type": "LineString", "coordinates": coordinates },
+            "properties": {
+                "id": edge,
+                "source": way.source,
+                "target": way.target,
+                "weight": way.weight,
+                "shortcut": way.contrated_previous.is_some(),
+                "level": attributes.level,
+                "name": attributes.name,
+                "ref": attributes.reference,
+                "highway": attributes.highway,
+            },
+        })
+    });
+    for (i, feature) in node_features.chain(edge_features).enumerate() {
+        if i > 0 {
+            write!(writer, ",")?;
+        }
+        writeln!(writer)?;
+        serde_json::to_writer(&mut *writer, &feature)?;
+    }
+    writeln!(writer, "\n]}}")
+}
+
+pub fn write_csv_nodes<W: Write>(
+    writer: &mut W,
+    graph: &FmiFile,
+    nodes: &[NodeId],
+) -> std::io::Result<()> {
+    writeln!(writer, "id,rank,wkt")?;
+    for node in nodes {
+        let coordinates = (graph.nodes[*node].latitude, graph.nodes[*node].longitude);
+        let wkt = to_wkt(&[coordinates]);
+        writeln!(
+            writer,
+            "{},{},{}",
+            node,
+            graph.nodes[*node].rank,
+            escape_csv(&wkt)
+        )?;
+    }
+    Ok(())
+}
+
+pub fn write_csv_edges<W: Write>(
+    writer: &mut W,
+    graph: &FmiFile,
+    edges: &[EdgeId],
+) -> std::io::Result<()> {
+    writeln!(
+        writer,
+        "id,source,target,weight,shortcut,level,name,ref,highway,wkt"
+    )?;
+    for edge in edges {
+        let way = &graph.edges[*edge];
+        let attributes = get_attributes(graph, *edge);
+        writeln!(
+            writer,
+            "{},{},{},{},{},{},{},{},{},{}",
+            edge,
+            way.source,
+            way.target,
+            way.weight,
+            way.contrated_previous.is_some(),
+            attributes.level.map_or(String::new(), |l| l.to_string()),
+            escape_csv(attributes.name),
+            escape_csv(attributes.reference),
+            escape_csv(attributes.highway),
+            escape_csv(&to_wkt(&get_coordinates(graph, *edge)))
+        )?;
+    }
+    Ok(())
+}
+
+pub fn write_graphml<W: Write>(
+    writer: &mut W,
+    graph: &FmiFile,
+    nodes: &[NodeId],
+    edges: &[EdgeId],
+) -> std::io::Result<()> {
+    writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        writer,
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">"
+    )?;
+    let keys = [
+        ("node", "lat", "double"),
+        ("node", "lng", "double"),
+        ("node", "rank", "long"),
+        ("edge", "weight", "long"),
+        ("edge", "shortcut", "boolean"),
+        ("edge", "level", "long"),
+        ("edge", "name", "string"),
+        ("edge", "ref", "string"),
+        ("edge", "highway", "string"),
+        ("edge", "wkt", "string"),
+    ];
+    for (domain, name, attribute_type) in keys.iter() {
+        writeln!(
+            writer,
+            "  <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>",
+            name, domain, name, attribute_type
+        )?;
+    }
+    writeln!(writer, "  <graph id=\"G\" edgedefault=\"directed\">")?;
+    for node in nodes {
+        let n = &graph.nodes[*node];
+        writeln!(
+            writer,
+            "    <node id=\"n{}\"><data key=\"lat\">{}</data><data key=\"lng\">{}</data><data key=\"rank\">{}</data></node>",
+            node, n.latitude, n.longitude, n.rank
+        )?;
+    }
+    for edge in edges {
+        let way = &graph.edges[*edge];
+        let attributes = get_attributes(graph, *edge);
+        write!(
+            writer,
+            "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\"><data key=\"weight\">{}</data><data key=\"shortcut\">{}</data>",
+            edge,
+            way.source,
+            way.target,
+            way.weight,
+            way.contrated_previous.is_some()
+        )?;
+        if let Some(level) = attributes.level {
+            write!(writer, "<data key=\"level\">{}</data>", level)?;
+        }
+        for (key, value) in [
+            ("name", attributes.name),
+            ("ref", attributes.reference),
+            ("highway", attributes.highway),
+        ]
+        .iter()
+        {
+            if !value.is_empty() {
+                write!(writer, "<data key=\"{}\">{}</data>", key, escape_xml(value))?;
+            }
+        }
+        let wkt = to_wkt(&get_coordinates(graph, *edge));
+        writeln!(writer, "<data key=\"wkt\">{}</data></edge>", wkt)?;
+    }
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</graphml>")
+}
+
+/// read a contracted graph and write it in all requested formats next to it
+pub fn run_export(arguments: &ExportArguments) {
+    let path = Path::new(&arguments.filename);
+    if !path.exists() {
+        println!("{} not found", arguments.filename);
+        std::process::exit(1);
+    }
+    let mut reader = BufReader::new(File::open(path).unwrap());
+    let graph: FmiFile = match deserialize_from(&mut reader) {
+        Ok(graph) => graph,
+        Err(e) => {
+            println!("could not read {}: {}", arguments.filename, e);
+            std::process::exit(1);
+        }
+    };
+    let (nodes, edges) = select(&graph, arguments.bbox.as_ref(), arguments.shortcuts);
+    println!("exported #nodes: {:?}", nodes.len());
+    println!("exported #edges: {:?}", edges.len());
+
+    let base = arguments.filename.trim_end_matches(".fmi");
+    let mut output_files = Vec::<String>::new();
+    for format in &arguments.formats {
+        match format {
+            ExportFormat::GeoJson => {
+                output_files.push(helper::write_text_to_disk(
+                    format!("{}.geojson", base),
+                    |w| write_geojson(w, &graph, &nodes, &edges),
+                ));
+            }
+            ExportFormat::Csv => {
+                output_files.push(helper::write_text_to_disk(
+                    format!("{}-nodes.csv", base),
+                    |w| write_csv_nodes(w, &graph, &nodes),
+                ));
+                output_files.push(helper::write_text_to_disk(
+                    format!("{}-edges.csv", base),
+                    |w| write_csv_edges(w, &graph, &edges),
+                ));
+            }
+            ExportFormat::GraphMl => {
+                output_files.push(helper::write_text_to_disk(
+                    format!("{}.graphml", base),
+                    |w| write_graphml(w, &graph, &nodes, &edges),
+                ));
+            }
+        }
+    }
+    for output_file in output_files {
+        println!("Output is written to: {}", output_file);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(latitude: f32, longitude: f32, rank: Rank) -> Node {
+        Node {
+            latitude,
+            longitude,
+            rank,
+        }
+    }
+
+    /// 0 -> 1 -> 2 with a shortcut over 1 and a shape between 0 and 1
+    fn graph() -> FmiFile {
+        let mut shortcut = Way::new(0, 2, 30);
+        shortcut.contrated_previous = Some(0);
+        shortcut.contrated_next = Some(1);
+        let mut geometry = Geometry::default();
+        for coordinates in [&[(48.5, 9.5)][..], &[], &[]].iter() {
+            geometry.push(coordinates);
+        }
+        FmiFile {
+            nodes: vec![node(48.0, 9.0, 1), node(49.0, 10.0, 0), node(50.0, 11.0, 2)],
+            up_offset: Vec::new(),
+            down_offset: Vec::new(),
+            down_index: Vec::new(),
+            edges: vec![Way::new(0, 1, 10), Way::new(1, 2, 20), shortcut],
+            grid_offset: Vec::new(),
+            grid: Vec::new(),
+            grid_bounds: GridBounds {
+                lat_min: 48.0,
+                lat_max: 50.0,
+                lng_min: 9.0,
+                lng_max: 11.0,
+            },
+            optimized_by: OptimizeBy::Time,
+            original_nodes: Vec::new(),
+            geometry,
+            elevations: Vec::new(),
+            edge_ways: vec![0, 0, INVALID_WAY],
+            ways: vec![WayInfo {
+                osm_id: 1,
+                name: 1,
+                reference: 0,
+                highway: 2,
+                ferry: false,
+                duration: None,
+            }],
+            strings: vec![
+                String::new(),
+                "Fish & \"Chips\", Street".to_string(),
+                "primary".to_string(),
+            ],
+            input_nodes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn selection() {
+        let graph = graph();
+        assert_eq!(select(&graph, None, true), (vec![0, 1, 2], vec![0, 1, 2]));
+        assert_eq!(select(&graph, None, false), (vec![0, 1, 2], vec![0, 1]));
+        let bbox = clip::parse_bbox("8.5,47.5,10.5,49.5").unwrap();
+        assert_eq!(select(&graph, Some(&bbox), true), (vec![0, 1], vec![0]));
+
+        // shortcuts are unpacked
+        assert_eq!(
+            get_coordinates(&graph, 2),
+            vec![(48.0, 9.0), (48.5, 9.5), (49.0, 10.0), (50.0, 11.0)]
+        );
+        assert_eq!(get_attributes(&graph, 2).level, Some(0));
+        assert_eq!(get_attributes(&graph, 0).highway, "primary");
+    }
+
+    #[test]
+    fn formats() {
+        let graph = graph();
+        let (nodes, edges) = select(&graph, None, true);
+
+        let mut output = Vec::<u8>::new();
+        write_geojson(&mut output, &graph, &nodes, &edges).unwrap();
+        let geojson: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        let features = geojson["features"].as_array().unwrap();
+        assert_eq!(features.len(), 6);
+        assert_eq!(
+            features[3]["geometry"]["coordinates"][1],
+            json!([9.5, 48.5])
+        );
+        assert_eq!(features[5]["properties"]["level"], json!(0));
+        assert_eq!(features[5]["properties"]["shortcut"], json!(true));
+
+        let mut output = Vec::<u8>::new();
+        write_csv_edges(&mut output, &graph, &edges).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[1],
+            "0,0,1,10,false,,\"Fish & \"\"Chips\"\", Street\",\"\",\"primary\",\"LINESTRING (9 48, 9.5 48.5, 10 49)\""
+        );
+
+        let mut output = Vec::<u8>::new();
+        write_graphml(&mut output, &graph, &nodes, &edges).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("<node ").count(), 3);
+        assert_eq!(output.matches("<edge ").count(), 3);
+        assert!(output.contains("<data key=\"name\">Fish &amp; &quot;Chips&quot;, Street</data>"));
+    }
+}
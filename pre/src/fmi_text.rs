@@ -0,0 +1,382 @@
+use super::*;
+use crate::id_mapping::IdMappingType;
+use osmpbfreader::Tags;
+use std::io::Write;
+use std::path::Path;
+
+/// street types of the fmi text format, the index is written as type.
+/// the first ones are used by the graphs of the university of stuttgart
+const STREET_TYPES: [&str; 26] = [
+    "",
+    "motorway",
+    "motorway_link",
+    "primary",
+    "primary_link",
+    "secondary",
+    "secondary_link",
+    "tertiary",
+    "tertiary_link",
+    "trunk",
+    "trunk_link",
+    "unclassified",
+    "residential",
+    "living_street",
+    "road",
+    "service",
+    "turning_circle",
+    "track",
+    "path",
+    "cycleway",
+    "footway",
+    "pedestrian",
+    "steps",
+    "bridleway",
+    "bus_guideway",
+    "busway",
+];
+
+/// files ending with .txt are read as fmi text graph,
+/// .fmi is the binary output of this program
+pub fn is_fmi_text(filename: &str) -> bool {
+    filename.ends_with(".txt")
+}
+
+/// street type of every way, unknown highway classes and ferries have the type 0
+pub fn get_way_types(ways: &[WayInfo], strings: &[String]) -> Vec<usize> {
+    ways.iter()
+        .map(|way| {
+            let highway = strings[way.highway].as_str();
+            match way.ferry {
+                true => 0,
+                false => STREET_TYPES.iter().position(|t| *t == highway).unwrap_or(0),
+            }
+        })
+        .collect()
+}
+
+/// next non-empty line, which is not a comment
+fn next_line<'a>(lines: &mut impl Iterator<Item = &'a str>, what: &str) -> Result<&'a str, String> {
+    lines
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .ok_or_else(|| format!("missing {}", what))
+}
+
+fn parse_value<T: std::str::FromStr>(value: Option<&str>, what: &str) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("missing {}", what))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid {} {}", what, value))
+}
+
+/// parse a graph in the fmi text format.
+/// costs and elevations are not used, the weights are calculated by the profiles
+/// from the coordinates, the street type and the maxspeed.
+/// every street type is stored as a single way without name
+// format: N, M, N lines `id osm-id lat lng elevation [level]`,
+// M lines `source target cost type maxspeed [child child]`
+pub fn parse_fmi(
+    content: &str,
+    profiles: &[Profile],
+    id_mapping: IdMappingType,
+) -> Result<OsmData, String> {
+    let mut lines = content.lines();
+    let amount_nodes: usize = parse_value(Some(next_line(&mut lines, "N")?), "N")?;
+    let amount_edges: usize = parse_value(Some(next_line(&mut lines, "M")?), "M")?;
+
+    let mut osm = OsmData {
+        nodes: Vec::with_capacity(amount_nodes),
+        node_tags: HashMap::new(),
+        full_edges: vec![Vec::new(); profiles.len()],
+        restrictions: Vec::new(),
+        way_nodes: HashMap::new(),
+        ways: Vec::new(),
+        strings: StringTable::new(),
+        osm_id_mapping: OsmIdMapping::new(id_mapping),
+        way_lengths: HashMap::new(),
+    };
+
+    let mut osm_ids = Vec::<i64>::with_capacity(amount_nodes);
+    let mut input_nodes = Vec::<Node>::with_capacity(amount_nodes);
+    for i in 0..amount_nodes {
+        let line = next_line(&mut lines, "node")?;
+        let mut values = line.split_whitespace();
+        let id: usize = parse_value(values.next(), "node id")?;
+        if id != i {
+            return Err(format!("node {} is not in order of the ids", id));
+        }
+        osm_ids.push(parse_value(values.next(), "osm-id")?);
+        input_nodes.push(Node {
+            latitude: parse_value(values.next(), "latitude")?,
+            longitude: parse_value(values.next(), "longitude")?,
+            rank: INVALID_RANK,
+        });
+    }
+    for osm_id in &osm_ids {
+        osm.osm_id_mapping.insert(*osm_id);
+    }
+    osm.osm_id_mapping.finish();
+    if osm.osm_id_mapping.len() != amount_nodes {
+        return Err("osm-ids are not unique".to_string());
+    }
+    // the nodes are stored at their own ids
+    let own_ids: Vec<NodeId> = osm_ids
+        .iter()
+        .map(|osm_id| osm.osm_id_mapping.get(*osm_id).unwrap())
+        .collect();
+    osm.nodes = input_nodes.clone();
+    for (node, own_id) in input_nodes.into_iter().zip(&own_ids) {
+        osm.nodes[*own_id] = node;
+    }
+
+    // one way per street type
+    let mut type_ways = HashMap::<usize, WayId>::new();
+    for _ in 0..amount_edges {
+        let line = next_line(&mut lines, "edge")?;
+        let mut values = line.split_whitespace();
+        let source: usize = parse_value(values.next(), "source")?;
+        let target: usize = parse_value(values.next(), "target")?;
+        if source >= amount_nodes || target >= amount_nodes {
+            return Err(format!("edge {} has an unknown node", line));
+        }
+        let _cost = values.next();
+        let street_type: isize = values.next().and_then(|v| v.parse().ok()).unwrap_or(0);
+        let maxspeed: isize = values.next().and_then(|v| v.parse().ok()).unwrap_or(0);
+        // shortcuts of a contracted graph have the type -1 and two child edges
+        let is_shortcut = values.any(|child| child.parse::<isize>().is_ok_and(|c| c >= 0));
+        if street_type < 0 || is_shortcut {
+            continue;
+        }
+        let street_type = street_type as usize;
+
+        let highway = STREET_TYPES.get(street_type).unwrap_or(&"");
+        let mut tags = Tags::new();
+        tags.insert("highway".to_string().into(), highway.to_string().into());
+        if maxspeed > 0 {
+            tags.insert("maxspeed".to_string().into(), maxspeed.to_string().into());
+        }
+        let way = *type_ways.entry(street_type).or_insert_with(|| {
+            osm.ways.push(WayInfo {
+                osm_id: 0,
+                name: 0,
+                reference: 0,
+                highway: osm.strings.get_id(highway),
+                ferry: false,
+                duration: None,
+            });
+            osm.ways.len() - 1
+        });
+
+        // the edges are already directed, oneways are not checked
+        for (profile, profile_edges) in profiles.iter().zip(osm.full_edges.iter_mut()) {
+            let settings = profile
+                .get_way_settings(&tags)
+                .zip(profile.get_surface_settings(&tags));
+            if let Some(((speed, _, penalty), (speed_factor, surface_penalty))) = settings {
+                profile_edges.push(OsmWay {
+                    source: own_ids[source],
+                    target: own_ids[target],
+                    speed,
+                    distance: 0,
+                    penalty: penalty + surface_penalty,
+                    way,
+                    ascent: 0,
+                    descent: 0,
+                    ferry: false,
+                    delay: 0,
+                    speed_factor,
+                    duration: None,
+                });
+            }
+        }
+    }
+    Ok(osm)
+}
+
+pub fn read_fmi(filename: &str, profiles: &[Profile], id_mapping: IdMappingType) -> OsmData {
+    let path = Path::new(&filename);
+    if !path.exists() {
+        println!("{} not found", filename);
+        std::process::exit(1);
+    }
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string());
+    match content.and_then(|content| parse_fmi(&content, profiles, id_mapping)) {
+        Ok(osm) => osm,
+        Err(e) => {
+            println!("could not parse {}: {}", filename, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// node line without level, elevations are written as 0 if unknown
+fn write_node<W: Write>(
+    writer: &mut W,
+    id: NodeId,
+    osm_id: i64,
+    node: &Node,
+    elevation: Option<f32>,
+) -> std::io::Result<()> {
+    write!(
+        writer,
+        "{} {} {} {} {}",
+        id,
+        osm_id,
+        node.latitude,
+        node.longitude,
+        elevation.map_or(0, |e| e.round() as i64)
+    )
+}
+
+/// write the uncontracted graph of a profile, the costs are the weights of the profile
+pub fn write_graph<W: Write>(
+    writer: &mut W,
+    nodes: &[Node],
+    osm_ids: &[i64],
+    elevations: &[Option<f32>],
+    full_edges: &[OsmWay],
+    edges: &[Way],
+    way_types: &[usize],
+) -> std::io::Result<()> {
+    writeln!(writer, "# Type : maxspeed")?;
+    writeln!(writer)?;
+    writeln!(writer, "{}", nodes.len())?;
+    writeln!(writer, "{}", edges.len())?;
+    for (i, node) in nodes.iter().enumerate() {
+        write_node(
+            writer,
+            i,
+            osm_ids[i],
+            node,
+            elevations.get(i).and_then(|e| *e),
+        )?;
+        writeln!(writer)?;
+    }
+    for (full_edge, edge) in full_edges.iter().zip(edges) {
+        writeln!(
+            writer,
+            "{} {} {} {} {}",
+            edge.source, edge.target, edge.weight, way_types[full_edge.way], full_edge.speed
+        )?;
+    }
+    Ok(())
+}
+
+/// write a contracted graph with the rank as level of the nodes.
+/// shortcuts have the type and maxspeed -1 and the ids of their two edges as children,
+/// all other edges have the children -1
+pub fn write_ch<W: Write>(
+    writer: &mut W,
+    graph: &FmiFile,
+    osm_ids: &[i64],
+    full_edges: &[OsmWay],
+    way_types: &[usize],
+) -> std::io::Result<()> {
+    writeln!(writer, "# Type : maxspeed : child edges")?;
+    writeln!(writer)?;
+    writeln!(writer, "{}", graph.nodes.len())?;
+    writeln!(writer, "{}", graph.edges.len())?;
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let elevation = graph.elevations.get(i).and_then(|e| *e);
+        write_node(writer, i, osm_ids[i], node, elevation)?;
+        writeln!(writer, " {}", node.rank)?;
+    }
+    for edge in &graph.edges {
+        write!(writer, "{} {} {} ", edge.source, edge.target, edge.weight)?;
+        match (edge.contrated_previous, edge.contrated_next, edge.id) {
+            (Some(previous), Some(next), _) => writeln!(writer, "-1 -1 {} {}", previous, next)?,
+            (_, _, Some(id)) => {
+                let full_edge = &full_edges[id];
+                writeln!(
+                    writer,
+                    "{} {} -1 -1",
+                    way_types[full_edge.way], full_edge.speed
+                )?
+            }
+            _ => writeln!(writer, "0 0 -1 -1")?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRAPH: &str = "# Id : 1\n\
+        # Type : maxspeed\n\
+        \n\
+        3\n\
+        3\n\
+        0 300 48.0 9.0 250\n\
+        1 100 48.0 9.001 251\n\
+        2 200 48.001 9.001 252\n\
+        0 1 74 12 0\n\
+        1 0 74 12 0\n\
+        1 2 111 20 0\n";
+
+    #[test]
+    fn input_files() {
+        assert!(is_fmi_text("stuttgart.txt"));
+        assert!(!is_fmi_text("stuttgart.car-time.fmi"));
+        assert!(!is_fmi_text("stuttgart.osm.pbf"));
+    }
+
+    #[test]
+    fn import() {
+        let profiles = vec![
+            Profile::builtin(TravelType::Car),
+            Profile::builtin(TravelType::Pedestrian),
+        ];
+        for id_mapping in [IdMappingType::Hash, IdMappingType::Sorted] {
+            let osm = parse_fmi(GRAPH, &profiles, id_mapping).unwrap();
+            assert_eq!(osm.nodes.len(), 3);
+            let first = osm.osm_id_mapping.get(300).unwrap();
+            assert_eq!(osm.nodes[first].longitude, 9.0);
+
+            // the footway is only used by pedestrians
+            assert_eq!(osm.full_edges[0].len(), 2);
+            assert_eq!(osm.full_edges[1].len(), 3);
+            assert!(osm.full_edges[0].iter().any(|e| e.source == first));
+            assert_eq!(osm.ways.len(), 2);
+            assert_eq!(get_way_types(&osm.ways, &osm.strings.strings), vec![12, 20]);
+        }
+
+        assert!(parse_fmi("2\n1\n0 1 48.0 9.0 0\n", &profiles, IdMappingType::Hash).is_err());
+        let duplicate = "2\n0\n0 1 48.0 9.0 0\n1 1 48.0 9.1 0\n";
+        assert!(parse_fmi(duplicate, &profiles, IdMappingType::Hash).is_err());
+        let unknown = "1\n1\n0 1 48.0 9.0 0\n0 1 1 12 0\n";
+        assert!(parse_fmi(unknown, &profiles, IdMappingType::Hash).is_err());
+    }
+
+    #[test]
+    fn export() {
+        let profiles = vec![Profile::builtin(TravelType::Car)];
+        let mut osm = parse_fmi(GRAPH, &profiles, IdMappingType::Hash).unwrap();
+        helper::calc_edge_distances(&mut osm.full_edges[0], &osm.nodes);
+        let edges = helper::edges_to_weight(&osm.full_edges[0], &profiles[0], OptimizeBy::Time);
+        let way_types = get_way_types(&osm.ways, &osm.strings.strings);
+        let elevations = vec![Some(250.4), None, Some(252.0)];
+
+        let mut output = Vec::<u8>::new();
+        write_graph(
+            &mut output,
+            &osm.nodes,
+            &osm.osm_id_mapping.get_osm_ids(),
+            &elevations,
+            &osm.full_edges[0],
+            &edges,
+            &way_types,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().skip(2).collect();
+        assert_eq!(lines[..3], ["3", "2", "0 300 48 9 250"]);
+        assert_eq!(lines[4], "2 200 48.001 9.001 252");
+        assert_eq!(lines[5], format!("0 1 {} 12 30", edges[0].weight));
+
+        // the written graph can be read again
+        let osm = parse_fmi(&output, &profiles, IdMappingType::Hash).unwrap();
+        assert_eq!(osm.full_edges[0].len(), 2);
+    }
+}
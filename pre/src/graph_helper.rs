@@ -161,7 +161,7 @@ mod tests {
         let up = get_up_edge_ids(0, &up_offset);
         assert_eq!(up, vec![0]);
         let down = get_down_edge_ids(0, &down_offset, &down_index);
-        assert_eq!(down, vec![]);
+        assert_eq!(down, Vec::<EdgeId>::new());
 
         let up = get_up_edge_ids(1, &up_offset);
         assert_eq!(up, vec![1]);
@@ -174,7 +174,7 @@ mod tests {
         assert_eq!(down, vec![1]);
 
         let up = get_up_edge_ids(3, &up_offset);
-        assert_eq!(up, vec![]);
+        assert_eq!(up, Vec::<EdgeId>::new());
         let down = get_down_edge_ids(3, &down_offset, &down_index);
         assert_eq!(down, vec![2]);
     }
@@ -1,27 +1,294 @@
 use super::*;
+use crate::id_mapping::IdMappingType;
 use bincode::serialize_into;
+use std::collections::HashSet;
 use std::fs::File;
-use std::io::BufWriter;
+use std::io::{BufWriter, Write};
+use std::str::FromStr;
 
-pub fn get_filename() -> String {
-    // check if arguments are right
-    let args: Vec<_> = std::env::args().collect();
-    if args.len() != 2 {
-        println!("Usage: {} pbf-file", args[0]);
-        std::process::exit(1);
+pub struct Arguments {
+    pub filename: String,
+    pub profiles: Vec<Profile>,
+    pub optimize_by: Vec<OptimizeBy>,
+    pub clip_area: Option<clip::ClipArea>,
+    /// keep complete ways crossing the border of the clip area
+    pub complete_ways: bool,
+    pub id_mapping: IdMappingType,
+    /// keep islands with at least this amount of nodes besides the largest component
+    pub min_island_size: Option<usize>,
+    /// directory with SRTM .hgt-files
+    pub elevation: Option<String>,
+    /// additionally write the graphs in the fmi text format
+    pub fmi_text: bool,
+    /// cache the weighted graphs and checkpoint the contraction
+    pub cache: bool,
+}
+
+fn print_usage(program: &str) -> ! {
+    println!(
+        "Usage: {} pbf-osm-txt-or-gr-file [--profile car|hgv|bicycle|pedestrian|wheelchair|all|<name>[,...]] [--optimize-by time|distance[,...]] [--profiles-file profiles.json] [--turn-restrictions] [--bbox lng_min,lat_min,lng_max,lat_max | --poly file.poly] [--complete-ways] [--id-mapping hash|sorted] [--keep-islands min-nodes] [--elevation srtm-directory] [--fmi-text] [--cache]",
+        program
+    );
+    println!("       {} --print-profiles", program);
+    println!(
+        "       {} export fmi-file [--format geojson|csv|graphml[,...]] [--bbox lng_min,lat_min,lng_max,lat_max] [--shortcuts]",
+        program
+    );
+    std::process::exit(1);
+}
+
+/// parse a comma separated list of values without duplicates
+fn parse_list<T>(value: &str) -> Result<Vec<T>, String>
+where
+    T: FromStr + PartialEq,
+    T::Err: ToString,
+{
+    let mut result = Vec::<T>::new();
+    for item in value.split(',') {
+        let item = item.trim().parse().map_err(|e: T::Err| e.to_string())?;
+        if !result.contains(&item) {
+            result.push(item);
+        }
+    }
+    Ok(result)
+}
+
+/// check if the export mode is used
+pub fn is_export() -> bool {
+    std::env::args().nth(1).as_deref() == Some("export")
+}
+
+/// parse the command line arguments of the export mode (default: geojson)
+pub fn get_export_arguments() -> export::ExportArguments {
+    let args: Vec<String> = std::env::args().collect();
+    let mut filename: Option<String> = None;
+    let mut formats = vec![export::ExportFormat::GeoJson];
+    let mut bbox: Option<clip::ClipArea> = None;
+    let mut shortcuts = false;
+
+    let mut i = 2;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--format" | "--bbox" if i + 1 >= args.len() => print_usage(&args[0]),
+            "--format" => {
+                i += 1;
+                formats = parse_list(&args[i]).unwrap_or_else(|e| {
+                    println!("{}", e);
+                    print_usage(&args[0])
+                });
+            }
+            "--bbox" => {
+                i += 1;
+                bbox = Some(clip::parse_bbox(&args[i]).unwrap_or_else(|e| {
+                    println!("{}", e);
+                    print_usage(&args[0])
+                }));
+            }
+            "--shortcuts" => shortcuts = true,
+            arg if arg.starts_with("--") || filename.is_some() => print_usage(&args[0]),
+            arg => filename = Some(arg.to_string()),
+        }
+        i += 1;
+    }
+
+    let filename = match filename {
+        Some(filename) => filename,
+        None => print_usage(&args[0]),
+    };
+    export::ExportArguments {
+        filename,
+        formats,
+        bbox,
+        shortcuts,
+    }
+}
+
+/// parse the command line arguments (defaults: car, time)
+/// every combination of the given profiles and metrics results in one graph
+pub fn get_arguments() -> Arguments {
+    let args: Vec<String> = std::env::args().collect();
+    let mut filename: Option<String> = None;
+    let mut profile_names = vec!["car".to_string()];
+    let mut optimize_by = vec![OptimizeBy::Time];
+    let mut profiles_file: Option<String> = None;
+    let mut turn_restrictions = false;
+    let mut clip_area: Option<clip::ClipArea> = None;
+    let mut complete_ways = false;
+    let mut id_mapping = IdMappingType::Hash;
+    let mut min_island_size: Option<usize> = None;
+    let mut elevation: Option<String> = None;
+    let mut fmi_text = false;
+    let mut cache = false;
+
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--profile" | "--optimize-by" | "--profiles-file" | "--bbox" | "--poly"
+            | "--id-mapping" | "--keep-islands" | "--elevation"
+                if i + 1 >= args.len() =>
+            {
+                print_usage(&args[0])
+            }
+            "--profile" => {
+                i += 1;
+                profile_names = parse_list(&args[i]).unwrap_or_else(|e| {
+                    println!("{}", e);
+                    print_usage(&args[0])
+                });
+            }
+            "--optimize-by" => {
+                i += 1;
+                optimize_by = parse_list(&args[i]).unwrap_or_else(|e| {
+                    println!("{}", e);
+                    print_usage(&args[0])
+                });
+            }
+            "--profiles-file" => {
+                i += 1;
+                profiles_file = Some(args[i].clone());
+            }
+            "--bbox" | "--poly" if clip_area.is_some() => print_usage(&args[0]),
+            "--bbox" => {
+                i += 1;
+                clip_area = Some(clip::parse_bbox(&args[i]).unwrap_or_else(|e| {
+                    println!("{}", e);
+                    print_usage(&args[0])
+                }));
+            }
+            "--poly" => {
+                i += 1;
+                clip_area = Some(clip::read_poly(&args[i]));
+            }
+            "--complete-ways" => complete_ways = true,
+            "--keep-islands" => {
+                i += 1;
+                min_island_size = Some(args[i].parse().unwrap_or_else(|_| {
+                    println!("invalid island size {}", args[i]);
+                    print_usage(&args[0])
+                }));
+            }
+            "--id-mapping" => {
+                i += 1;
+                id_mapping = args[i].parse().unwrap_or_else(|e| {
+                    println!("{}", e);
+                    print_usage(&args[0])
+                });
+            }
+            "--elevation" => {
+                i += 1;
+                elevation = Some(args[i].clone());
+            }
+            "--turn-restrictions" => turn_restrictions = true,
+            "--fmi-text" => fmi_text = true,
+            "--cache" => cache = true,
+            "--print-profiles" => {
+                let profiles = profile::builtin_profiles();
+                println!("{}", serde_json::to_string_pretty(&profiles).unwrap());
+                std::process::exit(0);
+            }
+            arg if arg.starts_with("--") || filename.is_some() => print_usage(&args[0]),
+            arg => filename = Some(arg.to_string()),
+        }
+        i += 1;
+    }
+
+    let filename = match filename {
+        Some(filename) => filename,
+        None => print_usage(&args[0]),
+    };
+    let mut profiles = profile::select_profiles(&profile_names, profiles_file.as_deref());
+    if turn_restrictions {
+        for profile in profiles.iter_mut().filter(|p| p.turns.is_none()) {
+            profile.turns = Some(profile::TurnSettings::default());
+        }
+    }
+    Arguments {
+        filename,
+        profiles,
+        optimize_by,
+        clip_area,
+        complete_ways,
+        id_mapping,
+        min_island_size,
+        elevation,
+        fmi_text,
+        cache,
     }
+}
+
+/// peak memory of the process in bytes (only available on linux)
+fn get_peak_memory() -> Option<usize> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    let kilobytes: usize = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kilobytes * 1024)
+}
 
-    // get filename
-    std::env::args_os().nth(1).unwrap().into_string().unwrap()
+pub fn print_memory_report(
+    osm_id_mapping: &OsmIdMapping,
+    id_mapping: IdMappingType,
+    nodes: &[Node],
+) {
+    let megabytes = |bytes: usize| bytes as f64 / 1024.0 / 1024.0;
+    println!(
+        "Memory of osm-id mapping ({}, {} ids): {:.1} MB",
+        id_mapping,
+        osm_id_mapping.len(),
+        megabytes(osm_id_mapping.memory_usage())
+    );
+    println!(
+        "Memory of nodes: {:.1} MB",
+        megabytes(std::mem::size_of_val(nodes))
+    );
+    if let Some(peak) = get_peak_memory() {
+        println!("Peak memory: {:.1} MB", megabytes(peak));
+    }
 }
 
-pub fn write_to_disk(filename: String, result: FmiFile) -> String {
-    let output_file = format!("{}{}", filename, ".fmi");
+/// name of the output file, e.g. `germany.osm.pbf` becomes `germany.car-time.fmi`
+pub fn get_output_filename(filename: &str, profile: &Profile, optimize_by: OptimizeBy) -> String {
+    let base = filename
+        .strip_suffix(".osm.pbf")
+        .or_else(|| filename.strip_suffix(".pbf"))
+        .or_else(|| filename.strip_suffix(".osm"))
+        .or_else(|| filename.strip_suffix(".txt"))
+        .unwrap_or(filename);
+    format!("{}.{}-{}.fmi", base, profile.name, optimize_by)
+}
+
+pub fn write_to_disk(output_file: String, result: FmiFile) -> String {
     let mut writer = BufWriter::new(File::create(&output_file).unwrap());
     serialize_into(&mut writer, &result).unwrap();
     output_file
 }
 
+/// write a text file with the given function
+pub fn write_text_to_disk<F>(output_file: String, write: F) -> String
+where
+    F: FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+{
+    let mut writer = BufWriter::new(File::create(&output_file).unwrap());
+    write(&mut writer).and_then(|_| writer.flush()).unwrap();
+    output_file
+}
+
+/// renumber the nodes of the edges, so that only used nodes are kept.
+/// returns the kept nodes and the mapping from old to new ids
+pub fn compact_nodes(full_edges: &mut [OsmWay], nodes: &[Node]) -> (Vec<Node>, Vec<NodeId>) {
+    let mut new_ids = vec![INVALID_NODE; nodes.len()];
+    let mut result = Vec::<Node>::new();
+    for edge in full_edges.iter_mut() {
+        for node in [&mut edge.source, &mut edge.target] {
+            if new_ids[*node] == INVALID_NODE {
+                new_ids[*node] = result.len();
+                result.push(nodes[*node].clone());
+            }
+            *node = new_ids[*node];
+        }
+    }
+    (result, new_ids)
+}
+
 /// get distance on earth surface using haversine formula
 fn calc_distance(lat_1: f32, long_1: f32, lat_2: f32, long_2: f32) -> f32 {
     let r: f32 = 6371.0; // constant used for meters
@@ -48,10 +315,195 @@ pub fn calc_edge_distances(full_edges: &mut Vec<OsmWay>, nodes: &[Node]) {
     });
 }
 
+/// length of the ways with a tagged duration, e.g. ferries, over the edges of all profiles.
+/// the length of a way is the sum of its segments, independent of the directions.
+/// has to be calculated before clipping, as the duration is the one of the whole way
+pub fn get_way_lengths(
+    full_edges: &[Vec<OsmWay>],
+    nodes: &[Node],
+    ways: &[WayInfo],
+) -> HashMap<WayId, usize> {
+    let mut segments = HashSet::<(WayId, NodeId, NodeId)>::new();
+    for edge in full_edges.iter().flatten() {
+        if ways[edge.way].duration.is_some() {
+            let (a, b) = (edge.source.min(edge.target), edge.source.max(edge.target));
+            segments.insert((edge.way, a, b));
+        }
+    }
+    let mut lengths = HashMap::<WayId, usize>::new();
+    for (way, a, b) in segments {
+        let (a, b) = (&nodes[a], &nodes[b]);
+        let distance = calc_distance(a.latitude, a.longitude, b.latitude, b.longitude);
+        *lengths.entry(way).or_insert(0) += (distance * DIST_MULTIPLICATOR as f32) as usize;
+    }
+    lengths
+}
+
+/// set the duration and the speed of the edges of ways with a tagged duration, e.g. ferries.
+/// the weight is taken from the duration, the rounded speed is only informative
+pub fn apply_durations(
+    full_edges: &mut [OsmWay],
+    ways: &[WayInfo],
+    way_lengths: &HashMap<WayId, usize>,
+) {
+    full_edges.par_iter_mut().for_each(|edge| {
+        if let (Some(duration), Some(length)) =
+            (ways[edge.way].duration, way_lengths.get(&edge.way))
+        {
+            let hours = duration as f32 / 3600.0;
+            let speed = (*length as f32 / DIST_MULTIPLICATOR as f32 / hours).round();
+            edge.speed = (speed as usize).max(1);
+            let share = edge.distance as f64 / (*length).max(1) as f64;
+            edge.duration = Some((duration as f64 * 1000.0 * share).round() as usize);
+        }
+    });
+}
+
+/// remove the edges through nodes, which can not be passed with the profile,
+/// and set the penalty for passing the target of the other edges
+pub fn apply_node_settings(
+    full_edges: &mut Vec<OsmWay>,
+    node_tags: &HashMap<NodeId, Tags>,
+    profile: &Profile,
+) {
+    let settings: HashMap<NodeId, Option<usize>> = node_tags
+        .iter()
+        .map(|(node, tags)| (*node, profile.get_node_settings(tags)))
+        .collect();
+    let is_blocked = |node: &NodeId| matches!(settings.get(node), Some(None));
+    full_edges.retain(|edge| !is_blocked(&edge.source) && !is_blocked(&edge.target));
+    full_edges.par_iter_mut().for_each(|edge| {
+        if let Some(Some(penalty)) = settings.get(&edge.target) {
+            edge.delay = *penalty;
+        }
+    });
+}
+
 /// convert osm-edges to normal ways
-pub fn edges_to_weight(full_edges: &[OsmWay]) -> Vec<Way> {
+pub fn edges_to_weight(
+    full_edges: &[OsmWay],
+    profile: &Profile,
+    optimize_by: OptimizeBy,
+) -> Vec<Way> {
     return full_edges
         .par_iter()
-        .map(|full_edge| Way::from(*full_edge))
+        .map(|full_edge| Way::from((*full_edge, profile, optimize_by)))
         .collect();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn way(duration: Option<usize>) -> WayInfo {
+        WayInfo {
+            osm_id: 0,
+            name: 0,
+            reference: 0,
+            highway: 0,
+            ferry: true,
+            duration,
+        }
+    }
+
+    fn edge(source: NodeId, target: NodeId, way: WayId) -> OsmWay {
+        OsmWay {
+            source,
+            target,
+            speed: 5,
+            distance: 5 * DIST_MULTIPLICATOR,
+            penalty: 0,
+            way,
+            ascent: 0,
+            descent: 0,
+            ferry: true,
+            delay: 0,
+            speed_factor: 100,
+            duration: None,
+        }
+    }
+
+    #[test]
+    fn node_settings() {
+        // 0 - 1 - 2 - 3 with traffic signals at 1 and a bollard at 3
+        let car = Profile::builtin(TravelType::Car);
+        let mut node_tags = HashMap::<NodeId, Tags>::new();
+        for (node, key, value) in [(1, "highway", "traffic_signals"), (3, "barrier", "bollard")] {
+            let tags = std::iter::once((key.to_string().into(), value.to_string().into()));
+            node_tags.insert(node, tags.collect());
+        }
+        let mut full_edges = vec![
+            edge(0, 1, 0),
+            edge(1, 0, 0),
+            edge(1, 2, 0),
+            edge(2, 1, 0),
+            edge(2, 3, 0),
+            edge(3, 2, 0),
+        ];
+        apply_node_settings(&mut full_edges, &node_tags, &car);
+        let result: Vec<(NodeId, NodeId, usize)> = full_edges
+            .iter()
+            .map(|edge| (edge.source, edge.target, edge.delay))
+            .collect();
+        assert_eq!(result, vec![(0, 1, 10), (1, 0, 0), (1, 2, 0), (2, 1, 10)]);
+    }
+
+    #[test]
+    fn durations() {
+        // 20 km in both directions taking 30 minutes, the second ferry has no duration
+        let ways = vec![way(Some(1800)), way(None)];
+        // 5 km between the nodes along the equator
+        let nodes: Vec<Node> = (0..7)
+            .map(|i| Node {
+                latitude: 0.0,
+                longitude: i as f32 * 0.044966,
+                rank: INVALID_RANK,
+            })
+            .collect();
+        let mut full_edges = vec![
+            edge(0, 1, 0),
+            edge(1, 0, 0),
+            edge(1, 2, 0),
+            edge(2, 1, 0),
+            edge(2, 3, 0),
+            edge(3, 2, 0),
+            edge(3, 4, 0),
+            edge(4, 3, 0),
+            edge(5, 6, 1),
+        ];
+        let way_lengths = get_way_lengths(&[full_edges.clone()], &nodes, &ways);
+        assert_eq!(way_lengths.len(), 1);
+        assert_eq!(
+            (way_lengths[&0] as f32 / DIST_MULTIPLICATOR as f32).round(),
+            20.0
+        );
+
+        // the clipped half of the ferry keeps its speed
+        let mut clipped = full_edges[..4].to_vec();
+        apply_durations(&mut clipped, &ways, &way_lengths);
+        assert!(clipped.iter().all(|edge| edge.speed == 40));
+
+        apply_durations(&mut full_edges, &ways, &way_lengths);
+        assert!(full_edges[..8].iter().all(|edge| edge.speed == 40));
+        assert_eq!(full_edges[8].speed, 5);
+
+        // the weight is taken from the duration of 7.5 minutes per edge
+        let car = Profile::builtin(TravelType::Car);
+        let seconds = |edge: &OsmWay| {
+            let weight = Way::from((*edge, &car, OptimizeBy::Time)).weight;
+            (weight as f32 * 3600.0 / DIST_MULTIPLICATOR as f32).round()
+        };
+        assert!(full_edges[..8].iter().all(|edge| seconds(edge) == 450.0));
+        assert_eq!(full_edges[8].duration, None);
+
+        // a short ferry of 50 m taking 2 minutes is not rounded to a speed
+        let ways = vec![way(Some(120))];
+        let mut short = vec![OsmWay {
+            distance: DIST_MULTIPLICATOR / 20,
+            ..edge(0, 1, 0)
+        }];
+        let way_lengths: HashMap<WayId, usize> = [(0, DIST_MULTIPLICATOR / 20)].into();
+        apply_durations(&mut short, &ways, &way_lengths);
+        assert_eq!(seconds(&short[0]), 120.0);
+    }
+}
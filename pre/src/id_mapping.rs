@@ -0,0 +1,148 @@
+use super::*;
+use std::fmt;
+use std::str::FromStr;
+
+/// strategy for mapping osm-ids to own ids
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IdMappingType {
+    /// fast lookups, but tens of bytes per node
+    Hash,
+    /// 8 bytes per node, lookups by binary search
+    Sorted,
+}
+
+impl FromStr for IdMappingType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "hash" => Ok(IdMappingType::Hash),
+            "sorted" => Ok(IdMappingType::Sorted),
+            _ => Err(format!("unknown id mapping {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for IdMappingType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IdMappingType::Hash => write!(f, "hash"),
+            IdMappingType::Sorted => write!(f, "sorted"),
+        }
+    }
+}
+
+/// mapping of osm-ids to own ids.
+/// all ids have to be inserted before calling finish, only then get is valid
+pub enum OsmIdMapping {
+    /// own ids in order of the first insertion
+    Hash(HashMap<i64, NodeId>),
+    /// the own id is the index in the sorted osm-ids
+    Sorted(Vec<i64>),
+}
+
+impl OsmIdMapping {
+    pub fn new(mapping_type: IdMappingType) -> Self {
+        match mapping_type {
+            IdMappingType::Hash => OsmIdMapping::Hash(HashMap::new()),
+            IdMappingType::Sorted => OsmIdMapping::Sorted(Vec::new()),
+        }
+    }
+
+    pub fn insert(&mut self, osm_id: i64) {
+        match self {
+            OsmIdMapping::Hash(mapping) => {
+                let next_id = mapping.len();
+                mapping.entry(osm_id).or_insert(next_id);
+            }
+            OsmIdMapping::Sorted(ids) => ids.push(osm_id),
+        }
+    }
+
+    pub fn finish(&mut self) {
+        match self {
+            OsmIdMapping::Hash(mapping) => mapping.shrink_to_fit(),
+            OsmIdMapping::Sorted(ids) => {
+                ids.par_sort_unstable();
+                ids.dedup();
+                ids.shrink_to_fit();
+            }
+        }
+    }
+
+    pub fn get(&self, osm_id: i64) -> Option<NodeId> {
+        match self {
+            OsmIdMapping::Hash(mapping) => mapping.get(&osm_id).copied(),
+            OsmIdMapping::Sorted(ids) => ids.binary_search(&osm_id).ok(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            OsmIdMapping::Hash(mapping) => mapping.len(),
+            OsmIdMapping::Sorted(ids) => ids.len(),
+        }
+    }
+
+    /// osm-id of every own id
+    pub fn get_osm_ids(&self) -> Vec<i64> {
+        match self {
+            OsmIdMapping::Hash(mapping) => {
+                let mut osm_ids = vec![0; mapping.len()];
+                for (osm_id, id) in mapping {
+                    osm_ids[*id] = *osm_id;
+                }
+                osm_ids
+            }
+            OsmIdMapping::Sorted(ids) => ids.clone(),
+        }
+    }
+
+    /// allocated memory in bytes
+    pub fn memory_usage(&self) -> usize {
+        match self {
+            // one control byte per bucket
+            OsmIdMapping::Hash(mapping) => {
+                mapping.capacity() * (std::mem::size_of::<(i64, NodeId)>() + 1)
+            }
+            OsmIdMapping::Sorted(ids) => ids.capacity() * std::mem::size_of::<i64>(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mapping_types() {
+        let osm_ids = [42, 7, 1_000_000_000_000, 42, 13];
+        for mapping_type in [IdMappingType::Hash, IdMappingType::Sorted] {
+            let mut mapping = OsmIdMapping::new(mapping_type);
+            for osm_id in osm_ids {
+                mapping.insert(osm_id);
+            }
+            mapping.finish();
+            assert_eq!(mapping.len(), 4);
+            assert_eq!(mapping.get(99), None);
+
+            let mut own_ids: Vec<NodeId> =
+                osm_ids.iter().map(|id| mapping.get(*id).unwrap()).collect();
+            assert_eq!(own_ids[0], own_ids[3]);
+            own_ids.sort_unstable();
+            own_ids.dedup();
+            assert_eq!(own_ids, vec![0, 1, 2, 3]);
+        }
+    }
+
+    #[test]
+    fn sorted_order() {
+        let mut mapping = OsmIdMapping::new(IdMappingType::Sorted);
+        for osm_id in [30, 10, 20] {
+            mapping.insert(osm_id);
+        }
+        mapping.finish();
+        assert_eq!(mapping.get(10), Some(0));
+        assert_eq!(mapping.get(30), Some(2));
+    }
+}
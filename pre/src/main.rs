@@ -1,68 +1,488 @@
+mod cache;
+mod chains;
+mod clip;
+mod components;
 mod constants;
 mod contraction;
 mod dijkstra;
+mod dimacs;
+mod elevation;
+mod export;
+mod fmi_text;
 mod graph_helper;
 mod grid;
 mod helper;
+mod id_mapping;
 mod min_heap;
 mod offset;
 mod ordering;
 mod osm_parsing;
 mod osm_pbf;
+mod osm_xml;
+mod profile;
 mod structs;
+mod turns;
 mod visited_list;
 
+use osmpbfreader::Tags;
 use rayon::prelude::*;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::atomic::{AtomicIsize, AtomicUsize, Ordering};
-use std::sync::RwLock;
 use std::time::Instant;
 
 use crate::constants::*;
+use crate::contraction::CheckpointSettings;
+use crate::elevation::Srtm;
+use crate::id_mapping::{IdMappingType, OsmIdMapping};
+use crate::profile::Profile;
 use crate::structs::*;
 use crate::visited_list::*;
 
-// Options: Car, Bicycle, Pedestrian, All
-const TRAVEL_TYPE: TravelType = TravelType::Car;
-// Options: Time, Distance
-const OPTIMIZE_BY: OptimizeBy = OptimizeBy::Time;
-
 fn main() {
     let overall_time = Instant::now();
 
-    let mut nodes = Vec::<Node>::new();
-    let mut full_edges = Vec::<OsmWay>::new();
-    let mut up_offset = Vec::<EdgeId>::new();
-    let mut down_offset = Vec::<EdgeId>::new();
-    let mut edges: Vec<Way>;
-    let mut grid_offset = Vec::<GridId>::new();
-    let mut grid = Vec::<NodeId>::new();
+    // write an existing graph in other formats
+    if helper::is_export() {
+        export::run_export(&helper::get_export_arguments());
+        println!("Overall: {:?}", overall_time.elapsed());
+        return;
+    }
+
+    let arguments = helper::get_arguments();
+    let profiles = &arguments.profiles;
+
+    // DIMACS graphs are contracted with their own weights, the profiles are not used
+    if dimacs::is_dimacs(&arguments.filename) {
+        let output_file = dimacs::get_output_filename(&arguments.filename);
+        let checkpoints = arguments
+            .cache
+            .then(|| cache::get_checkpoints(&output_file));
+        let result = contract_dimacs(
+            &arguments.filename,
+            arguments.optimize_by[0],
+            checkpoints.as_ref(),
+        );
+        let output_file = helper::write_to_disk(output_file, result);
+        println!("Overall: {:?}", overall_time.elapsed());
+        println!("Output is written to: {}", output_file);
+        return;
+    }
+
+    // the input is not read again, if the weighted graphs of all profiles are cached.
+    // the fmi text export needs the input
+    let mut output_files = Vec::<String>::new();
+    if arguments.cache && !arguments.fmi_text && cache::is_cached(&arguments) {
+        for profile in profiles {
+            for optimize_by in &arguments.optimize_by {
+                println!(
+                    "Profile: {}, optimize by: {} (cached)",
+                    profile.name, optimize_by
+                );
+                let output_file =
+                    helper::get_output_filename(&arguments.filename, profile, *optimize_by);
+                let graph = cache::read_graph(&cache::get_cache_filename(&output_file));
+                let srtm = (arguments.elevation.as_ref())
+                    .map(|directory| Srtm::load(directory, &graph.nodes));
+                let checkpoints = cache::get_checkpoints(&output_file);
+                let mut result =
+                    contract_weighted_graph(graph, arguments.min_island_size, Some(&checkpoints));
+                if let Some(srtm) = &srtm {
+                    add_elevations(&mut result, srtm);
+                }
+                output_files.push(helper::write_to_disk(output_file, result));
+            }
+        }
+        println!("Overall: {:?}", overall_time.elapsed());
+        for output_file in output_files {
+            println!("Output is written to: {}", output_file);
+        }
+        return;
+    }
+
+    let input_time = Instant::now();
+    let mut osm = read_input(&arguments.filename, profiles, arguments.id_mapping);
+    println!("Reading input in: {:?}", input_time.elapsed());
+
+    // only keep the edges inside of the given area
+    if let Some(area) = &arguments.clip_area {
+        let clip_time = Instant::now();
+        let inside = clip::get_inside_nodes(&osm.nodes, area);
+        for profile_edges in osm.full_edges.iter_mut() {
+            clip::clip_edges(profile_edges, &inside, arguments.complete_ways);
+        }
+        println!("Clipping in: {:?}", clip_time.elapsed());
+    }
+
+    // elevation of every node from SRTM-tiles
+    let srtm = arguments.elevation.as_ref().map(|directory| {
+        let elevation_time = Instant::now();
+        let srtm = Srtm::load(directory, &osm.nodes);
+        println!("Loading elevation in: {:?}", elevation_time.elapsed());
+        srtm
+    });
+    let node_elevations = srtm.as_ref().map(|srtm| srtm.get_elevations(&osm.nodes));
+
+    // needed for the fmi text export
+    let (osm_ids, way_types) = match arguments.fmi_text {
+        true => (
+            osm.osm_id_mapping.get_osm_ids(),
+            fmi_text::get_way_types(&osm.ways, &osm.strings.strings),
+        ),
+        false => (Vec::new(), Vec::new()),
+    };
+
+    let mut full_edges = std::mem::take(&mut osm.full_edges);
+    for (profile, profile_edges) in profiles.iter().zip(full_edges.iter_mut()) {
+        let (profile_nodes, input_nodes, geometry, turn_restrictions) =
+            prepare_profile(profile_edges, profile, &osm, node_elevations.as_deref());
+        let profile_osm_ids: Vec<i64> = match arguments.fmi_text {
+            true => input_nodes.iter().map(|node| osm_ids[*node]).collect(),
+            false => Vec::new(),
+        };
+
+        for optimize_by in &arguments.optimize_by {
+            println!("Profile: {}, optimize by: {}", profile.name, optimize_by);
+            let output_file =
+                helper::get_output_filename(&arguments.filename, profile, *optimize_by);
+            let mut graph = weight_graph(
+                profile_nodes.clone(),
+                profile_edges,
+                &geometry,
+                profile,
+                *optimize_by,
+                &turn_restrictions,
+            );
+            graph.ways = osm.ways.clone();
+            graph.strings = osm.strings.strings.clone();
+            let checkpoints = arguments.cache.then(|| {
+                graph.key = cache::get_cache_key(&arguments, profile, *optimize_by);
+                cache::write_graph(&cache::get_cache_filename(&output_file), &graph);
+                cache::get_checkpoints(&output_file)
+            });
+            let mut result =
+                contract_weighted_graph(graph, arguments.min_island_size, checkpoints.as_ref());
+            if let Some(srtm) = &srtm {
+                add_elevations(&mut result, srtm);
+            }
+
+            if arguments.fmi_text {
+                let base = output_file.trim_end_matches(".fmi");
+                let weights = helper::edges_to_weight(profile_edges, profile, *optimize_by);
+                let elevations = srtm
+                    .as_ref()
+                    .map_or(Vec::new(), |srtm| srtm.get_elevations(&profile_nodes));
+                output_files.push(helper::write_text_to_disk(
+                    format!("{}-graph.txt", base),
+                    |writer| {
+                        fmi_text::write_graph(
+                            writer,
+                            &profile_nodes,
+                            &profile_osm_ids,
+                            &elevations,
+                            profile_edges,
+                            &weights,
+                            &way_types,
+                        )
+                    },
+                ));
+                let osm_ids: Vec<i64> = (result.input_nodes.iter())
+                    .map(|node| profile_osm_ids[*node])
+                    .collect();
+                output_files.push(helper::write_text_to_disk(
+                    format!("{}-ch.txt", base),
+                    |writer| {
+                        fmi_text::write_ch(writer, &result, &osm_ids, profile_edges, &way_types)
+                    },
+                ));
+            }
+
+            // save results to disk
+            output_files.push(helper::write_to_disk(output_file, result));
+        }
+    }
+
+    println!("Overall: {:?}", overall_time.elapsed());
+    helper::print_memory_report(&osm.osm_id_mapping, arguments.id_mapping, &osm.nodes);
+    for output_file in output_files {
+        println!("Output is written to: {}", output_file);
+    }
+}
 
-    // storing mapping of own-ids and osm-ids
-    let mut osm_id_mapping = HashMap::<i64, usize>::new();
+/// read the ways of all profiles, their nodes and the restrictions.
+/// fmi text graphs are read without restrictions
+fn read_input(filename: &str, profiles: &[Profile], id_mapping: IdMappingType) -> OsmData {
+    let mut osm = match fmi_text::is_fmi_text(filename) {
+        true => fmi_text::read_fmi(filename, profiles, id_mapping),
+        false => read_osm(filename, profiles, id_mapping),
+    };
+    osm.way_lengths = helper::get_way_lengths(&osm.full_edges, &osm.nodes, &osm.ways);
+    osm
+}
 
-    let filename = helper::get_filename();
+/// read the ways of all profiles, their nodes and the restrictions from a pbf- or xml-file
+fn read_osm(filename: &str, profiles: &[Profile], id_mapping: IdMappingType) -> OsmData {
+    let mut osm = OsmData {
+        nodes: Vec::new(),
+        node_tags: HashMap::new(),
+        full_edges: vec![Vec::new(); profiles.len()],
+        restrictions: Vec::new(),
+        way_nodes: HashMap::new(),
+        ways: Vec::new(),
+        strings: StringTable::new(),
+        osm_id_mapping: OsmIdMapping::new(id_mapping),
+        way_lengths: HashMap::new(),
+    };
 
-    let pbf_time = Instant::now();
-    let mut pbf = osm_pbf::get_pbf(&filename);
+    let mut input = osm_pbf::get_input(filename);
+    if profiles
+        .iter()
+        .any(|p| p.turns.as_ref().is_some_and(|t| t.restrictions))
+    {
+        osm_pbf::read_restrictions(&mut input, &mut osm.restrictions, &mut osm.way_nodes);
+        println!("restrictions: {:?}", osm.restrictions.len());
+    }
     // store all way-IDs that are having the "highway" tag. with speed-limit
-    osm_pbf::read_edges(&mut pbf, &mut full_edges, &mut osm_id_mapping);
-    let amount_nodes = osm_id_mapping.len();
+    osm_pbf::read_edges(
+        &mut input,
+        profiles,
+        &mut osm.full_edges,
+        &mut osm.osm_id_mapping,
+        &mut osm.way_nodes,
+        &mut osm.ways,
+        &mut osm.strings,
+    );
     // store all geo-information about nodes
-    osm_pbf::read_ways(&mut pbf, &mut nodes, &mut osm_id_mapping);
-    println!("Reading PBF in: {:?}", pbf_time.elapsed());
+    osm_pbf::read_ways(
+        &mut input,
+        &mut osm.nodes,
+        &mut osm.node_tags,
+        &osm.osm_id_mapping,
+    );
+    osm
+}
+
+/// apply the node settings, climbs and durations to the edges of a single profile
+/// and collapse their chains. returns the used nodes, their index in the nodes of the input,
+/// the shape of the edges and the resolved turn restrictions
+fn prepare_profile(
+    profile_edges: &mut Vec<OsmWay>,
+    profile: &Profile,
+    osm: &OsmData,
+    node_elevations: Option<&[Option<f32>]>,
+) -> (Vec<Node>, Vec<NodeId>, Geometry, Vec<TurnRestriction>) {
+    helper::apply_node_settings(profile_edges, &osm.node_tags, profile);
+    if let Some(node_elevations) = node_elevations {
+        elevation::calc_edge_climbs(profile_edges, node_elevations);
+    }
+    // only keep the nodes used by this profile
+    let (profile_nodes, input_mapping) = helper::compact_nodes(profile_edges, &osm.nodes);
+    helper::calc_edge_distances(profile_edges, &profile_nodes);
+    helper::apply_durations(profile_edges, &osm.ways, &osm.way_lengths);
+
+    let mut turn_restrictions = match &profile.turns {
+        Some(turns) if turns.restrictions => turns::resolve_restrictions(
+            &osm.restrictions,
+            &osm.way_nodes,
+            &osm.osm_id_mapping,
+            &input_mapping,
+            &osm.ways,
+            profile.travel_type,
+        ),
+        _ => Vec::new(),
+    };
 
+    // collapse chains of degree-2 nodes, nodes of restrictions are kept
+    let chains_time = Instant::now();
+    let mut protected = vec![false; profile_nodes.len()];
+    for node in turn_restrictions.iter().flat_map(|r| r.nodes.iter()) {
+        protected[*node] = true;
+    }
+    let geometry = chains::compress_chains(profile_edges, &profile_nodes, &protected);
+    let (profile_nodes, node_mapping) = helper::compact_nodes(profile_edges, &profile_nodes);
+    for restriction in turn_restrictions.iter_mut() {
+        for node in restriction.nodes.iter_mut() {
+            *node = node_mapping[*node];
+        }
+    }
+    println!("Compressing chains in: {:?}", chains_time.elapsed());
+
+    let mut input_nodes = vec![INVALID_NODE; profile_nodes.len()];
+    for (input_node, node) in input_mapping.iter().enumerate() {
+        if *node != INVALID_NODE && node_mapping[*node] != INVALID_NODE {
+            input_nodes[node_mapping[*node]] = input_node;
+        }
+    }
+    (profile_nodes, input_nodes, geometry, turn_restrictions)
+}
+
+/// weight the graph of a single profile, the key, ways and strings are set by the caller
+fn weight_graph(
+    mut nodes: Vec<Node>,
+    full_edges: &[OsmWay],
+    geometry: &Geometry,
+    profile: &Profile,
+    optimize_by: OptimizeBy,
+    turn_restrictions: &[TurnRestriction],
+) -> WeightedGraph {
     let weight_time = Instant::now();
-    helper::calc_edge_distances(&mut full_edges, &nodes);
-    edges = helper::edges_to_weight(&full_edges);
+    let mut edges = helper::edges_to_weight(full_edges, profile, optimize_by);
     println!("Getting weights in: {:?}", weight_time.elapsed());
 
+    // until the contraction, the ids of the edges point to their original edge
+    edges
+        .par_iter_mut()
+        .enumerate()
+        .for_each(|(i, edge)| edge.id = Some(i));
+
+    // edge-based graph for respecting turns
+    let mut original_nodes = Vec::<NodeId>::new();
+    if let Some(turns) = &profile.turns {
+        let turns_time = Instant::now();
+        let graph = turns::build_edge_based_graph(
+            full_edges,
+            &edges,
+            &nodes,
+            geometry,
+            turn_restrictions,
+            turns,
+            optimize_by,
+        );
+        // every turn passes the original edge of its target
+        let original_edges = graph.2;
+        nodes = graph.0;
+        edges = graph.1;
+        edges
+            .par_iter_mut()
+            .for_each(|edge| edge.id = Some(original_edges[edge.target]));
+        original_nodes = original_edges
+            .iter()
+            .map(|edge| full_edges[*edge].target)
+            .collect();
+        println!("Building edge-based graph in: {:?}", turns_time.elapsed());
+    }
+
+    WeightedGraph {
+        key: String::new(),
+        edge_ids: edges.iter().map(|edge| edge.id.unwrap()).collect(),
+        nodes,
+        edges,
+        original_nodes,
+        optimized_by: optimize_by,
+        geometry: geometry.clone(),
+        edge_ways: full_edges.iter().map(|edge| edge.way).collect(),
+        ways: Vec::new(),
+        strings: Vec::new(),
+    }
+}
+
+/// contract and index a weighted graph
+fn contract_weighted_graph(
+    mut graph: WeightedGraph,
+    min_island_size: Option<usize>,
+    checkpoints: Option<&CheckpointSettings>,
+) -> FmiFile {
+    for (edge, id) in graph.edges.iter_mut().zip(&graph.edge_ids) {
+        edge.id = Some(*id);
+    }
+    let mut result = contract_graph(
+        graph.nodes,
+        graph.edges,
+        graph.original_nodes,
+        graph.optimized_by,
+        min_island_size,
+        checkpoints,
+    );
+
+    // shape and way of the remaining original edges
+    for edge in &result.edges {
+        match edge.id {
+            Some(id) => {
+                result.geometry.push(graph.geometry.get(id));
+                result.edge_ways.push(graph.edge_ways[id]);
+            }
+            None => {
+                result.geometry.push(&[]);
+                result.edge_ways.push(INVALID_WAY);
+            }
+        }
+    }
+    result.ways = graph.ways;
+    result.strings = graph.strings;
+    result
+}
+
+/// elevation of the nodes and the shapes of the edges
+fn add_elevations(result: &mut FmiFile, srtm: &Srtm) {
+    result.elevations = srtm.get_elevations(&result.nodes);
+    result.geometry.elevations = (result.geometry.coordinates.iter())
+        .map(|(lat, lng)| srtm.get_elevation(*lat, *lng))
+        .collect();
+}
+
+/// contract a DIMACS graph with its own weights. no islands are removed,
+/// because the node ids of the output have to match the ids of the input
+fn contract_dimacs(
+    filename: &str,
+    optimize_by: OptimizeBy,
+    checkpoints: Option<&CheckpointSettings>,
+) -> FmiFile {
+    let input_time = Instant::now();
+    let (nodes, mut edges) = dimacs::read_dimacs(filename);
+    println!("Reading input in: {:?}", input_time.elapsed());
+    edges
+        .par_iter_mut()
+        .enumerate()
+        .for_each(|(i, edge)| edge.id = Some(i));
+    // every component has at least one node
+    contract_graph(nodes, edges, Vec::new(), optimize_by, Some(1), checkpoints)
+}
+
+/// remove the islands, contract the graph and generate the grid.
+/// the ids of the edges have to point to their original edges, after the contraction
+/// they still do for the remaining original edges and are None for the shortcuts.
+/// the checkpoint is removed after a successful contraction
+fn contract_graph(
+    mut nodes: Vec<Node>,
+    mut edges: Vec<Way>,
+    mut original_nodes: Vec<NodeId>,
+    optimize_by: OptimizeBy,
+    min_island_size: Option<usize>,
+    checkpoints: Option<&CheckpointSettings>,
+) -> FmiFile {
+    let mut up_offset = Vec::<EdgeId>::new();
+    let mut down_offset = Vec::<EdgeId>::new();
+    let mut grid_offset = Vec::<GridId>::new();
+    let mut grid = Vec::<NodeId>::new();
+
+    // remove small islands, which can not be routed to.
+    // the nodes of the profile graph are tracked until then
+    let edge_based = !original_nodes.is_empty();
+    if !edge_based {
+        original_nodes = (0..nodes.len()).collect();
+    }
+    let components_time = Instant::now();
+    components::restrict_to_components(
+        &mut nodes,
+        &mut edges,
+        &mut original_nodes,
+        min_island_size,
+    );
+    println!(
+        "Strongly connected components in: {:?}",
+        components_time.elapsed()
+    );
+    let input_nodes = match edge_based {
+        true => original_nodes.clone(),
+        false => std::mem::take(&mut original_nodes),
+    };
+
     // generate offset arrays
     let mut down_index =
-        offset::generate_offsets(&mut edges, &mut up_offset, &mut down_offset, amount_nodes);
+        offset::generate_offsets(&mut edges, &mut up_offset, &mut down_offset, nodes.len());
 
     println!("original #nodes: {:?}", nodes.len());
     println!("original #edges: {:?}", edges.len());
+    let original_edges: Vec<EdgeId> = edges.iter().map(|edge| edge.id.unwrap()).collect();
 
     // contraction hierarchies
     let contraction_time = Instant::now();
@@ -72,8 +492,22 @@ fn main() {
         &mut up_offset,
         &mut down_offset,
         &mut down_index,
+        checkpoints,
+        MIN_SORTED_NODES,
     );
     println!("Contraction in: {:?}", contraction_time.elapsed());
+    if let Some(checkpoints) = checkpoints {
+        if std::path::Path::new(&checkpoints.filename).exists() {
+            std::fs::remove_file(&checkpoints.filename).unwrap();
+        }
+    }
+
+    edges.par_iter_mut().for_each(|edge| {
+        edge.id = match (edge.contrated_previous, edge.id) {
+            (None, Some(id)) => Some(original_edges[id]),
+            _ => None,
+        }
+    });
 
     // generate grid
     let grid_time = Instant::now();
@@ -84,7 +518,7 @@ fn main() {
     println!("new #edges: {:?}", edges.len());
 
     // combine everything
-    let result = FmiFile {
+    FmiFile {
         nodes,
         edges,
         up_offset,
@@ -93,12 +527,234 @@ fn main() {
         grid,
         grid_offset,
         grid_bounds,
-        optimized_by: OPTIMIZE_BY,
-    };
+        optimized_by: optimize_by,
+        original_nodes,
+        // filled for graphs of osm-data
+        geometry: Geometry::default(),
+        // filled if elevation data is given
+        elevations: Vec::new(),
+        edge_ways: Vec::new(),
+        // filled for all graphs of the same input
+        ways: Vec::new(),
+        strings: Vec::new(),
+        input_nodes,
+    }
+}
 
-    // save results to disk
-    let output_file = helper::write_to_disk(filename, result);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    println!("Overall: {:?}", overall_time.elapsed());
-    println!("Output is written to: {}", output_file);
+    const SMALL: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/small.osm");
+    const SMALL_DIMACS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/small.gr");
+    const ISLAND_DIMACS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/island.gr");
+
+    fn get_graph(
+        filename: &str,
+        travel_type: TravelType,
+        optimize_by: OptimizeBy,
+    ) -> (FmiFile, Vec<OsmWay>) {
+        let profiles = vec![Profile::builtin(travel_type)];
+        let mut osm = read_input(filename, &profiles, IdMappingType::Hash);
+        let mut profile_edges = std::mem::take(&mut osm.full_edges[0]);
+        let (nodes, _, geometry, turn_restrictions) =
+            prepare_profile(&mut profile_edges, &profiles[0], &osm, None);
+        let mut graph = weight_graph(
+            nodes,
+            &profile_edges,
+            &geometry,
+            &profiles[0],
+            optimize_by,
+            &turn_restrictions,
+        );
+        graph.ways = osm.ways;
+        graph.strings = osm.strings.strings;
+        (contract_weighted_graph(graph, Some(1), None), profile_edges)
+    }
+
+    /// weight of the shortest path between two coordinates of the fixture
+    fn get_weight(graph: &FmiFile, from: (f32, f32), to: (f32, f32)) -> Option<usize> {
+        let find = |(lat, lng): (f32, f32)| {
+            graph
+                .nodes
+                .iter()
+                .position(|n| (n.latitude - lat).abs() < 1e-6 && (n.longitude - lng).abs() < 1e-6)
+                .unwrap()
+        };
+        let mut dijkstra = dijkstra::Dijkstra::new(graph.nodes.len());
+        dijkstra
+            .find_path(
+                find(from),
+                find(to),
+                &graph.up_offset,
+                &graph.edges,
+                false,
+                0,
+            )
+            .map(|(_, weight)| weight)
+    }
+
+    #[test]
+    fn small_xml() {
+        let (n1, n3, n4, n6) = ((48.0, 9.0), (48.0, 9.002), (47.999, 9.0), (47.999, 9.002));
+        let (n2, n5) = ((48.0, 9.001), (47.999, 9.001));
+
+        // the bollard blocks the direct way and the oneway can only be passed forwards
+        let car = get_graph(SMALL, TravelType::Car, OptimizeBy::Distance).0;
+        let car_detour = get_weight(&car, n1, n4).unwrap();
+        assert!(get_weight(&car, n3, n6).is_some());
+        assert_eq!(get_weight(&car, n6, n3), None);
+        assert_eq!(get_weight(&car, n4, n1), None);
+        // the deleted way is not used
+        assert_eq!(car.ways.len(), 4);
+        assert!(car.strings.contains(&"Nordstraße".to_string()));
+
+        // pedestrians use the footway and pass the bollard
+        let pedestrian = get_graph(SMALL, TravelType::Pedestrian, OptimizeBy::Distance).0;
+        let direct = get_weight(&pedestrian, n1, n4).unwrap();
+        assert!(car_detour > 3 * direct);
+        assert!(get_weight(&pedestrian, n4, n1).unwrap().abs_diff(direct) <= 1);
+        assert!(get_weight(&pedestrian, n2, n5).unwrap().abs_diff(direct) <= 1);
+        assert!(get_weight(&pedestrian, n6, n3).is_some());
+    }
+
+    #[test]
+    fn fmi_text_export() {
+        let (car, full_edges) = get_graph(SMALL, TravelType::Car, OptimizeBy::Time);
+        let osm_ids: Vec<i64> = car.input_nodes.iter().map(|n| *n as i64 + 100).collect();
+        let way_types = fmi_text::get_way_types(&car.ways, &car.strings);
+        let mut output = Vec::<u8>::new();
+        fmi_text::write_ch(&mut output, &car, &osm_ids, &full_edges, &way_types).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        let lines: Vec<&str> = output.lines().skip(2).collect();
+        assert_eq!(lines.len(), 2 + car.nodes.len() + car.edges.len());
+        let edge_lines = &lines[2 + car.nodes.len()..];
+        for (edge, line) in car.edges.iter().zip(edge_lines) {
+            let values: Vec<&str> = line.split_whitespace().collect();
+            assert_eq!(values.len(), 7);
+            match edge.contrated_previous {
+                Some(previous) => assert_eq!(values[3..6], ["-1", "-1", &previous.to_string()]),
+                None => assert!(values[3] == "11" || values[3] == "12" || values[3] == "3"),
+            }
+        }
+
+        // the contracted graph can be read as input again, without its shortcuts
+        let profiles = vec![Profile::builtin(TravelType::Car)];
+        let osm = fmi_text::parse_fmi(&output, &profiles, IdMappingType::Hash).unwrap();
+        assert_eq!(osm.nodes.len(), car.nodes.len());
+        // the same original edges between the same coordinates with the same speeds
+        let key = |node: &Node| (node.latitude.to_bits(), node.longitude.to_bits());
+        let mut expected: Vec<_> = (car.edges.iter())
+            .filter(|edge| edge.contrated_previous.is_none())
+            .map(|edge| {
+                let speed = full_edges[edge.id.unwrap()].speed;
+                (
+                    key(&car.nodes[edge.source]),
+                    key(&car.nodes[edge.target]),
+                    speed,
+                )
+            })
+            .collect();
+        let mut imported: Vec<_> = (osm.full_edges[0].iter())
+            .map(|edge| {
+                let (source, target) = (&osm.nodes[edge.source], &osm.nodes[edge.target]);
+                (key(source), key(target), edge.speed)
+            })
+            .collect();
+        expected.sort_unstable();
+        imported.sort_unstable();
+        assert_eq!(imported, expected);
+    }
+
+    #[test]
+    fn traffic_signals() {
+        // waiting at the traffic signals makes the way into 6 slower than the way out of it
+        let car = get_graph(SMALL, TravelType::Car, OptimizeBy::Time).0;
+        let into = get_weight(&car, (47.999, 9.0), (47.999, 9.002)).unwrap();
+        let out = get_weight(&car, (47.999, 9.002), (47.999, 9.0)).unwrap();
+        assert!(into > out);
+    }
+
+    /// weight of the shortest path in the contracted graph,
+    /// by an upward search from the start and a downward search from the end
+    fn get_ch_weight(graph: &FmiFile, start: NodeId, end: NodeId) -> Option<usize> {
+        let search = |start: NodeId, forward: bool| {
+            let mut weights = vec![usize::MAX; graph.nodes.len()];
+            let mut heap = std::collections::BinaryHeap::new();
+            weights[start] = 0;
+            heap.push(std::cmp::Reverse((0, start)));
+            while let Some(std::cmp::Reverse((weight, node))) = heap.pop() {
+                if weight > weights[node] {
+                    continue;
+                }
+                for edge in &graph.edges {
+                    let (from, to) = match forward {
+                        true => (edge.source, edge.target),
+                        false => (edge.target, edge.source),
+                    };
+                    let upward = graph.nodes[to].rank > graph.nodes[from].rank;
+                    if from == node && upward && weight + edge.weight < weights[to] {
+                        weights[to] = weight + edge.weight;
+                        heap.push(std::cmp::Reverse((weights[to], to)));
+                    }
+                }
+            }
+            weights
+        };
+        let (up, down) = (search(start, true), search(end, false));
+        (0..graph.nodes.len())
+            .filter(|n| up[*n] != usize::MAX && down[*n] != usize::MAX)
+            .map(|n| up[n] + down[n])
+            .min()
+    }
+
+    #[test]
+    fn dimacs() {
+        let (nodes, mut edges) = dimacs::read_dimacs(SMALL_DIMACS);
+        assert_eq!(nodes.len(), 9);
+        assert_eq!(edges.len(), 25);
+        for (i, edge) in edges.iter_mut().enumerate() {
+            edge.id = Some(i);
+        }
+        let graph = contract_graph(
+            nodes,
+            edges.clone(),
+            Vec::new(),
+            OptimizeBy::Time,
+            None,
+            None,
+        );
+        assert_eq!(graph.nodes.len(), 9);
+        assert!(graph.nodes.iter().all(|n| n.rank != INVALID_RANK));
+
+        // the contracted graph has the same shortest paths as the input
+        let mut up_offset = Vec::<EdgeId>::new();
+        let mut down_offset = Vec::<EdgeId>::new();
+        offset::generate_offsets(&mut edges, &mut up_offset, &mut down_offset, 9);
+        for start in 0..9 {
+            for end in 0..9 {
+                let mut dijkstra = dijkstra::Dijkstra::new(9);
+                let expected = dijkstra
+                    .find_path(start, end, &up_offset, &edges, false, 0)
+                    .map(|(_, weight)| weight);
+                assert_eq!(get_ch_weight(&graph, start, end), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn dimacs_island() {
+        let (nodes, _) = dimacs::read_dimacs(ISLAND_DIMACS);
+        let graph = contract_dimacs(ISLAND_DIMACS, OptimizeBy::Time, None);
+
+        // the unreachable node 4 is kept with its id
+        assert_eq!(graph.nodes.len(), 4);
+        for (node, input) in graph.nodes.iter().zip(nodes.iter()) {
+            assert_eq!(node.latitude, input.latitude);
+            assert_eq!(node.longitude, input.longitude);
+        }
+        assert_eq!(get_ch_weight(&graph, 3, 2), Some(17));
+        assert_eq!(get_ch_weight(&graph, 0, 3), None);
+    }
 }
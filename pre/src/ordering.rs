@@ -74,7 +74,7 @@ pub fn calculate_heuristics(
 
     let mut nodes: Vec<NodeId> = (0..amount_nodes).collect();
 
-    let thread_count = num_cpus::get();
+    let thread_count = rayon::current_num_threads();
     let chunk_size = (amount_nodes + thread_count - 1) / thread_count;
 
     if chunk_size > 0 {
@@ -117,7 +117,7 @@ pub fn update_neighbor_heuristics(
     down_offset: &[EdgeId],
     down_index: &[EdgeId],
 ) {
-    let thread_count = num_cpus::get();
+    let thread_count = rayon::current_num_threads();
     let chunk_size = (neighbors.len() + thread_count - 1) / thread_count;
 
     if chunk_size > 0 {
@@ -145,9 +145,10 @@ pub fn update_neighbor_heuristics(
     }
 }
 
-/// get independent set of graph using heuristic
-pub fn get_independent_set(
-    remaining_nodes: &BTreeSet<NodeId>,
+/// nodes of the subset without a neighbor with a lower or equal heuristic.
+/// of equal neighbors, the last one in the subset is used
+fn get_local_minima(
+    subset: &[NodeId],
     heuristics: &[AtomicIsize],
     minimas_bool: &mut VisitedList,
     edges: &[Way],
@@ -155,19 +156,9 @@ pub fn get_independent_set(
     down_offset: &[EdgeId],
     down_index: &[NodeId],
 ) -> Vec<NodeId> {
-    let mut remaining_nodes_vector: Vec<NodeId> = remaining_nodes.iter().copied().collect();
-    let subset: Vec<NodeId> = if remaining_nodes.len() > 10_000 {
-        // sort remaining_nodes via heuristic
-        remaining_nodes_vector.par_sort_by_key(|&node| heuristics[node].load(Ordering::Relaxed));
-        // take lower 1/4
-        (remaining_nodes_vector[0..remaining_nodes_vector.len() / 4]).to_vec()
-    } else {
-        remaining_nodes_vector
-    };
-
     minimas_bool.unvisit_all();
     // mark all neighbors with greater equal value as invalid
-    for node in &subset {
+    for node in subset {
         for neighbor in
             graph_helper::get_all_neighbours(*node, edges, up_offset, down_offset, down_index)
         {
@@ -190,6 +181,45 @@ pub fn get_independent_set(
     result
 }
 
+/// get independent set of graph using heuristic.
+/// with more than min_sorted_nodes remaining, only the nodes with the lowest heuristic are used
+#[allow(clippy::too_many_arguments)]
+pub fn get_independent_set(
+    remaining_nodes: &BTreeSet<NodeId>,
+    heuristics: &[AtomicIsize],
+    minimas_bool: &mut VisitedList,
+    edges: &[Way],
+    up_offset: &[EdgeId],
+    down_offset: &[EdgeId],
+    down_index: &[NodeId],
+    min_sorted_nodes: usize,
+) -> Vec<NodeId> {
+    let mut remaining_nodes_vector: Vec<NodeId> = remaining_nodes.iter().copied().collect();
+    let mut get_minima = |subset: &[NodeId]| {
+        get_local_minima(
+            subset,
+            heuristics,
+            minimas_bool,
+            edges,
+            up_offset,
+            down_offset,
+            down_index,
+        )
+    };
+    if remaining_nodes.len() > min_sorted_nodes {
+        // sort remaining_nodes via heuristic, equal ones by their id
+        remaining_nodes_vector
+            .par_sort_unstable_by_key(|&node| (heuristics[node].load(Ordering::Relaxed), node));
+        // take lower 1/4
+        let minima = get_minima(&remaining_nodes_vector[0..remaining_nodes_vector.len() / 4]);
+        // none is left, if all of them have equal neighbors outside of the subset
+        if !minima.is_empty() {
+            return minima;
+        }
+    }
+    get_minima(&remaining_nodes_vector)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -242,6 +272,7 @@ mod tests {
             &up_offset,
             &down_offset,
             &down_index,
+            MIN_SORTED_NODES,
         );
 
         let mut expected_minima = Vec::<NodeId>::new();
@@ -274,6 +305,7 @@ mod tests {
             &up_offset,
             &down_offset,
             &down_index,
+            MIN_SORTED_NODES,
         );
 
         let mut expected_minima = Vec::<NodeId>::new();
@@ -283,5 +315,20 @@ mod tests {
         expected_minima.push(8);
 
         assert_eq!(minima, expected_minima);
+
+        // the sorted lower quarter 0, 1 has only equal neighbors, all nodes are used instead
+        let remaining_nodes: BTreeSet<NodeId> = (0..amount_nodes).collect();
+        let heuristics: Vec<AtomicIsize> = (0..amount_nodes).map(|_| AtomicIsize::new(1)).collect();
+        let minima = get_independent_set(
+            &remaining_nodes,
+            &heuristics,
+            &mut minimas_bool,
+            &edges,
+            &up_offset,
+            &down_offset,
+            &down_index,
+            0,
+        );
+        assert_eq!(minima, vec![8]);
     }
 }
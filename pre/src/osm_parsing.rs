@@ -1,41 +1,138 @@
 use super::*;
+use osmpbfreader::Tags;
 
-// parse max-speed to valid weight (fallback is highway-tag)
-pub fn parse_speed(max_speed: &str, highway: &str) -> usize {
-    match max_speed.parse::<usize>() {
-        Ok(ok) => ok,
-        Err(_e) => match resolve_max_speed(max_speed) {
-            Ok(ok) => ok,
-            Err(_e) => aproximate_speed_limit(highway),
+// walking speed in km/h
+const WALK_SPEED: usize = 7;
+
+/// implicit speed limits in km/h per country: (country, urban, rural, motorway)
+// info from: https://wiki.openstreetmap.org/wiki/Default_speed_limits
+const IMPLICIT_MAX_SPEEDS: [(&str, usize, usize, usize); 49] = [
+    ("AL", 40, 80, 110),
+    ("AR", 40, 110, 130),
+    ("AT", 50, 100, 130),
+    ("AU", 50, 100, 110),
+    ("BA", 50, 80, 130),
+    ("BE", 50, 70, 120),
+    ("BG", 50, 90, 140),
+    ("BR", 60, 90, 110),
+    ("BY", 60, 90, 110),
+    ("CA", 50, 80, 100),
+    ("CH", 50, 80, 120),
+    ("CL", 60, 100, 120),
+    ("CN", 50, 70, 120),
+    ("CY", 50, 80, 100),
+    ("CZ", 50, 90, 130),
+    ("DE", 50, 100, 130),
+    ("DK", 50, 80, 130),
+    ("EE", 50, 90, 110),
+    ("ES", 50, 90, 120),
+    ("FI", 50, 80, 120),
+    ("FR", 50, 80, 130),
+    ("GB", 48, 97, 113),
+    ("GR", 50, 90, 130),
+    ("HR", 50, 90, 130),
+    ("HU", 50, 90, 130),
+    ("IE", 50, 80, 120),
+    ("IL", 50, 80, 110),
+    ("IS", 50, 80, 90),
+    ("IT", 50, 90, 130),
+    ("JP", 60, 60, 100),
+    ("LT", 50, 90, 130),
+    ("LU", 50, 90, 130),
+    ("LV", 50, 90, 110),
+    ("ME", 50, 80, 130),
+    ("MK", 50, 80, 130),
+    ("NL", 50, 80, 100),
+    ("NO", 50, 80, 110),
+    ("NZ", 50, 100, 100),
+    ("PL", 50, 90, 140),
+    ("PT", 50, 90, 120),
+    ("RO", 50, 90, 130),
+    ("RS", 50, 80, 130),
+    ("RU", 60, 90, 110),
+    ("SE", 50, 70, 110),
+    ("SI", 50, 90, 130),
+    ("SK", 50, 90, 130),
+    ("TR", 50, 90, 120),
+    ("UA", 50, 90, 130),
+    ("US", 40, 89, 105),
+];
+
+/// implicit speed limits of countries not listed in IMPLICIT_MAX_SPEEDS
+const DEFAULT_IMPLICIT_MAX_SPEEDS: (usize, usize, usize) = (50, 90, 120);
+
+/// parse max-speed to a valid speed in km/h (None if it can not be resolved).
+/// of multiple values the lowest one is used
+pub fn parse_max_speed(max_speed: &str) -> Option<usize> {
+    max_speed
+        .split(';')
+        .filter_map(|s| resolve_max_speed(s.trim()))
+        .min()
+}
+
+/// resolves a single max-speed value.
+/// "none", "signals" and "variable" are not resolved, so the speed of the highway class is used
+// info from: https://wiki.openstreetmap.org/wiki/Key:maxspeed
+fn resolve_max_speed(s: &str) -> Option<usize> {
+    let s = s
+        .strip_prefix("zone:maxspeed=")
+        .or_else(|| s.strip_prefix("maxspeed="))
+        .unwrap_or(s);
+    match s {
+        "" | "none" | "signals" | "variable" => None,
+        "walk" | "Schrittgeschwindigkeit" => Some(WALK_SPEED),
+        _ => match s.split_once(':') {
+            Some((country, kind)) => get_implicit_max_speed(&country.to_uppercase(), kind),
+            None => parse_speed_with_unit(s),
         },
     }
 }
 
-/// resolves the int value from a dirty string that can't be resolved by default parsing
-fn resolve_max_speed(s: &str) -> Result<usize, &str> {
-    match s {
-        "DE:motorway" => Ok(120),
-        "DE:rural" | "AT:rural" => Ok(100),
-        "DE:urban" | "AT:urban" | "CZ:urban" => Ok(50),
-        "maxspeed=50" => Ok(50),
-        "50;" | "50b" => Ok(50),
-        "DE:living_street" => Ok(30),
-        "30 kph" => Ok(30),
-        "zone:maxspeed=de:30" => Ok(30),
-        "DE:zone:30" => Ok(30),
-        "DE:zone30" => Ok(30),
-        "30 mph" => Ok(30),
-        "20:forward" => Ok(20),
-        "10 mph" => Ok(10),
-        "5 mph" => Ok(7),
-        "DE:walk" | "walk" | "Schrittgeschwindigkeit" => Ok(7),
-        _ => Err("none"),
+/// parse a numeric speed with an optional unit (km/h, mph or knots)
+fn parse_speed_with_unit(s: &str) -> Option<usize> {
+    let number_end = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let value = s[..number_end].parse::<f64>().ok()?;
+    let factor = match s[number_end..].trim() {
+        "" | "km/h" | "kmh" | "kph" | "kmph" => 1.0,
+        "mph" => 1.609_344,
+        "knots" => 1.852,
+        _ => return None,
+    };
+    match (value * factor).round() as usize {
+        0 => None,
+        speed => Some(speed),
+    }
+}
+
+/// get the implicit speed limit of a country, e.g. for "FR:urban" or "DE:zone30"
+fn get_implicit_max_speed(country: &str, kind: &str) -> Option<usize> {
+    let (urban, rural, motorway) = IMPLICIT_MAX_SPEEDS
+        .iter()
+        .find(|(code, _, _, _)| *code == country)
+        .map(|(_, urban, rural, motorway)| (*urban, *rural, *motorway))
+        .unwrap_or(DEFAULT_IMPLICIT_MAX_SPEEDS);
+    match kind {
+        "urban" => Some(urban),
+        "rural" | "trunk" => Some(rural),
+        "motorway" => Some(motorway),
+        "living_street" | "walk" => Some(WALK_SPEED),
+        "bicycle_road" | "cyclestreet" => Some(30),
+        "nsl_restricted" => Some(48),
+        "nsl_single" => Some(97),
+        "nsl_dual" => Some(113),
+        _ => {
+            // zones like "zone30", "zone:30" or only "30"
+            let zone = kind.strip_prefix("zone").unwrap_or(kind);
+            parse_speed_with_unit(zone.trim_start_matches(':'))
+        }
     }
 }
 
 /// approximates the speed limit based on given highway type
 // infos from https://wiki.openstreetmap.org/wiki/Key:highway
-fn aproximate_speed_limit(s: &str) -> usize {
+pub fn aproximate_speed_limit(s: &str) -> usize {
     match s {
         "motorway" => 120,
         "motorway_link" => 60,
@@ -66,8 +163,9 @@ pub fn get_street_type(s: &str, has_sidewalk: bool) -> TravelType {
         "tertiary" | "tertiary_link" => TravelType::CarBicycle,
         "cycleway" => TravelType::Bicycle,
         "trail" | "track" | "path" => TravelType::BicyclePedestrian,
-        "elevator" | "platform" | "corridor" => TravelType::Pedestrian,
-        "bus_stop" | "bridleway" | "steps" | "pedestrian" | "footway" => TravelType::Pedestrian,
+        "elevator" | "platform" | "corridor" => TravelType::Wheelchair,
+        "bus_stop" | "pedestrian" | "footway" => TravelType::Wheelchair,
+        "bridleway" | "steps" => TravelType::Pedestrian,
         "unclassified" => TravelType::All,
         "residential" | "living_street" => TravelType::All,
         "service" | "road" => TravelType::All,
@@ -84,12 +182,595 @@ pub fn get_street_type(s: &str, has_sidewalk: bool) -> TravelType {
     result
 }
 
-/// get directions from on_way
-// info from: https://wiki.openstreetmap.org/wiki/Forward_%26_backward,_left_%26_right#Identifying_the_direction_of_a_way
-pub fn parse_one_way(s: &str) -> (bool, bool) {
+/// access-keys of the travel type from general to specific
+// info from: https://wiki.openstreetmap.org/wiki/Key:access#Transport_mode_restrictions
+fn get_access_keys(travel_type: TravelType) -> &'static [&'static str] {
+    match travel_type {
+        TravelType::Car => &["access", "vehicle", "motor_vehicle", "motorcar"],
+        TravelType::Hgv => &["access", "vehicle", "motor_vehicle", "hgv"],
+        TravelType::Bicycle => &["access", "vehicle", "bicycle"],
+        TravelType::Pedestrian => &["access", "foot"],
+        TravelType::Wheelchair => &["access", "foot", "wheelchair"],
+        _ => &["access"],
+    }
+}
+
+/// resolve a single access-value
+fn resolve_access(s: &str) -> Option<Access> {
+    match s {
+        "yes" | "permissive" | "designated" | "official" | "dismount" => Some(Access::Yes),
+        "destination" | "customers" | "delivery" | "permit" => Some(Access::Destination),
+        "no" | "private" | "agricultural" | "forestry" | "use_sidepath" | "discouraged" => {
+            Some(Access::No)
+        }
+        _ => None,
+    }
+}
+
+/// evaluate the access-tags of the travel type, the most specific known value wins.
+/// returns the access and the key it is taken from
+pub fn parse_access(tags: &Tags, travel_type: TravelType) -> Option<(Access, &'static str)> {
+    get_access_keys(travel_type).iter().rev().find_map(|key| {
+        tags.get(*key)
+            .and_then(|value| resolve_access(value.trim()))
+            .map(|access| (access, *key))
+    })
+}
+
+/// check if the way has a sidewalk on any side
+pub fn has_sidewalk(tags: &Tags) -> bool {
+    match tags.get("sidewalk") {
+        Some(sidewalk) => !matches!(sidewalk.trim(), "None" | "none" | "No" | "no"),
+        None => false,
+    }
+}
+
+/// speed factor in percent of a surface-value (None for unknown values)
+// info from: https://wiki.openstreetmap.org/wiki/Key:surface
+fn get_surface_factor(s: &str) -> Option<usize> {
+    match s {
+        "asphalt" | "paved" | "concrete" | "concrete:plates" | "chipseal" => Some(100),
+        "paving_stones" | "concrete:lanes" | "metal" => Some(90),
+        "compacted" | "fine_gravel" => Some(85),
+        "wood" | "sett" | "unpaved" => Some(75),
+        "gravel" | "ground" | "dirt" | "earth" => Some(70),
+        "cobblestone" | "unhewn_cobblestone" | "pebblestone" => Some(60),
+        "grass" | "grass_paver" => Some(50),
+        "sand" | "mud" => Some(40),
+        _ => None,
+    }
+}
+
+/// speed factor in percent of a smoothness-value (None for unknown values)
+// info from: https://wiki.openstreetmap.org/wiki/Key:smoothness
+fn get_smoothness_factor(s: &str) -> Option<usize> {
     match s {
-        "yes" => (true, false),
-        "-1" => (true, true),
-        _ => (false, false),
+        "excellent" | "good" => Some(100),
+        "intermediate" => Some(90),
+        "bad" => Some(70),
+        "very_bad" => Some(50),
+        "horrible" => Some(30),
+        "very_horrible" | "impassable" => Some(10),
+        _ => None,
+    }
+}
+
+/// speed factor in percent of a tracktype-value (None for unknown values)
+// info from: https://wiki.openstreetmap.org/wiki/Key:tracktype
+fn get_tracktype_factor(s: &str) -> Option<usize> {
+    match s {
+        "grade1" => Some(100),
+        "grade2" => Some(85),
+        "grade3" => Some(70),
+        "grade4" => Some(55),
+        "grade5" => Some(40),
+        _ => None,
+    }
+}
+
+/// speed factor in percent for riding on the surface of the way.
+/// the worst of surface, smoothness and tracktype is used (100 if nothing is known)
+pub fn parse_surface(tags: &Tags) -> usize {
+    let get = |key: &str, factor: fn(&str) -> Option<usize>| {
+        tags.get(key).and_then(|value| factor(value.trim()))
+    };
+    [
+        get("surface", get_surface_factor),
+        get("smoothness", get_smoothness_factor),
+        get("tracktype", get_tracktype_factor),
+    ]
+    .iter()
+    .flatten()
+    .min()
+    .map_or(100, |factor| *factor)
+}
+
+/// parse an incline to its absolute value in percent, degrees are converted.
+/// "up" and "down" have no value
+// info from: https://wiki.openstreetmap.org/wiki/Key:incline
+pub fn parse_incline(s: &str) -> Option<f32> {
+    let s = s.trim();
+    let incline = if let Some(percent) = s.strip_suffix('%') {
+        percent.trim().parse::<f32>().ok()?
+    } else if let Some(degrees) = s.strip_suffix('°') {
+        degrees.trim().parse::<f32>().ok()?.to_radians().tan() * 100.0
+    } else {
+        s.parse::<f32>().ok()?
+    };
+    Some(incline.abs())
+}
+
+/// parse a weight to tonnes, e.g. "7.5", "7.5 t", "7500 kg" or "10 st".
+/// values like "none" are not resolved
+// info from: https://wiki.openstreetmap.org/wiki/Key:maxweight
+pub fn parse_weight(s: &str) -> Option<f32> {
+    let s = s.trim();
+    // units per tonne
+    let (value, units) = if let Some(kg) = s.strip_suffix("kg") {
+        (kg, 1000.0)
+    } else if let Some(lbs) = s.strip_suffix("lbs") {
+        (lbs, 2204.6)
+    } else if let Some(st) = s.strip_suffix("st") {
+        // short tons
+        (st, 1.1023)
+    } else {
+        (s.strip_suffix('t').unwrap_or(s), 1.0)
+    };
+    let value = value.trim().parse::<f32>().ok()?;
+    Some(value / units).filter(|weight| *weight > 0.0)
+}
+
+/// parse a length to meters, e.g. "3.5", "3.5 m", "12 ft" or "12'6\"".
+/// values like "default" or "below_default" are not resolved
+// info from: https://wiki.openstreetmap.org/wiki/Key:maxheight
+pub fn parse_length(s: &str) -> Option<f32> {
+    let s = s.trim();
+    let length = if let Some((feet, inches)) = s.split_once('\'') {
+        let inches = inches.trim().trim_end_matches('"').trim();
+        let inches = match inches.is_empty() {
+            true => 0.0,
+            false => inches.parse::<f32>().ok()?,
+        };
+        (feet.trim().parse::<f32>().ok()? * 12.0 + inches) * 0.0254
+    } else if let Some(feet) = s.strip_suffix("ft") {
+        feet.trim().parse::<f32>().ok()? * 0.3048
+    } else {
+        s.strip_suffix('m')
+            .unwrap_or(s)
+            .trim()
+            .parse::<f32>()
+            .ok()?
+    };
+    Some(length).filter(|length| *length > 0.0)
+}
+
+/// check if the way is known to be unpaved
+// info from: https://wiki.openstreetmap.org/wiki/Key:surface
+pub fn is_unpaved(tags: &Tags) -> bool {
+    let paved_track = matches!(
+        tags.get("tracktype").map(|s| s.trim()),
+        None | Some("grade1")
+    );
+    let unpaved_surface = matches!(
+        tags.get("surface").map(|s| s.trim()),
+        Some(
+            "unpaved"
+                | "compacted"
+                | "fine_gravel"
+                | "gravel"
+                | "pebblestone"
+                | "rock"
+                | "ground"
+                | "dirt"
+                | "earth"
+                | "grass"
+                | "sand"
+                | "mud"
+                | "woodchips"
+                | "salt"
+                | "snow"
+                | "ice"
+        )
+    );
+    !paved_track || unpaved_surface
+}
+
+/// check if the way has a cycle lane or track or is designated for bicycles
+// info from: https://wiki.openstreetmap.org/wiki/Key:cycleway
+pub fn has_cycleway(tags: &Tags) -> bool {
+    let is_cycleway = |key: &str| {
+        matches!(
+            tags.get(key).map(|s| s.trim()),
+            Some("lane" | "track" | "opposite_lane" | "opposite_track")
+        )
+    };
+    [
+        "cycleway",
+        "cycleway:both",
+        "cycleway:left",
+        "cycleway:right",
+    ]
+    .iter()
+    .any(|key| is_cycleway(key))
+        || tags
+            .get("bicycle")
+            .is_some_and(|s| s.trim() == "designated")
+}
+
+/// check if the way is a ferry or car-shuttle route
+// info from: https://wiki.openstreetmap.org/wiki/Tag:route%3Dferry
+pub fn is_ferry(tags: &Tags) -> bool {
+    matches!(
+        tags.get("route").map(|s| s.trim()),
+        Some("ferry") | Some("shuttle_train")
+    )
+}
+
+/// parse a duration to seconds ("mm", "hh:mm", "hh:mm:ss" or ISO 8601 like "PT1H30M")
+// info from: https://wiki.openstreetmap.org/wiki/Key:duration
+pub fn parse_duration(s: &str) -> Option<usize> {
+    let s = s.trim();
+    let seconds = if let Some(iso) = s.strip_prefix('P') {
+        let mut seconds = 0.0;
+        let mut is_time = false;
+        let mut number = String::new();
+        for c in iso.chars() {
+            match c {
+                'T' if number.is_empty() => is_time = true,
+                '0'..='9' | '.' => number.push(c),
+                ',' => number.push('.'),
+                unit => {
+                    let value: f32 = number.parse().ok()?;
+                    number.clear();
+                    seconds += value
+                        * match (unit, is_time) {
+                            ('D', false) => 86400.0,
+                            ('H', true) => 3600.0,
+                            ('M', true) => 60.0,
+                            ('S', true) => 1.0,
+                            _ => return None,
+                        };
+                }
+            }
+        }
+        if !number.is_empty() {
+            return None;
+        }
+        seconds as usize
+    } else {
+        let parts = s
+            .split(':')
+            .map(|part| part.trim().parse::<usize>())
+            .collect::<Result<Vec<usize>, _>>()
+            .ok()?;
+        match parts.as_slice() {
+            [minutes] => minutes * 60,
+            [hours, minutes] => hours * 3600 + minutes * 60,
+            [hours, minutes, seconds] => hours * 3600 + minutes * 60 + seconds,
+            _ => return None,
+        }
+    };
+    Some(seconds).filter(|seconds| *seconds > 0)
+}
+
+/// check if a restriction relation applies to the travel type, returns if it is an "only_"-restriction
+// info from: https://wiki.openstreetmap.org/wiki/Relation:restriction
+pub fn parse_restriction(tags: &Tags, travel_type: TravelType) -> Option<bool> {
+    let modes: &[&str] = match travel_type {
+        TravelType::Car => &["motorcar", "motor_vehicle", "vehicle"],
+        TravelType::Hgv => &["hgv", "motor_vehicle", "vehicle"],
+        TravelType::Bicycle => &["bicycle", "vehicle"],
+        TravelType::Pedestrian | TravelType::Wheelchair => &["foot"],
+        _ => &[],
+    };
+    if let Some(except) = tags.get("except") {
+        if except.split(';').any(|e| modes.contains(&e.trim())) {
+            return None;
+        }
+    }
+    // pedestrians are not bound by general restrictions
+    let restriction = modes
+        .iter()
+        .find_map(|mode| tags.get(format!("restriction:{}", mode).as_str()))
+        .or_else(|| match travel_type {
+            TravelType::Pedestrian | TravelType::Wheelchair => None,
+            _ => tags.get("restriction"),
+        })?
+        .trim();
+    if restriction.starts_with("only_") {
+        Some(true)
+    } else if restriction.starts_with("no_") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// resolve a single oneway-value to the allowed directions (forward, backward)
+fn resolve_one_way(s: &str) -> Option<(bool, bool)> {
+    match s {
+        "yes" | "true" | "1" => Some((true, false)),
+        "-1" | "reverse" => Some((false, true)),
+        "no" | "false" | "0" => Some((true, true)),
+        // direction changes over time, so it is not usable in any direction
+        "reversible" | "alternating" => Some((false, false)),
+        _ => None,
+    }
+}
+
+/// get the allowed directions (forward, backward) of a way for the travel type
+// info from: https://wiki.openstreetmap.org/wiki/Key:oneway
+// and: https://wiki.openstreetmap.org/wiki/Forward_%26_backward,_left_%26_right#Identifying_the_direction_of_a_way
+pub fn parse_one_way(tags: &Tags, travel_type: TravelType) -> (bool, bool) {
+    let get_value = |key: &str| tags.get(key).and_then(|v| resolve_one_way(v.trim()));
+    // pedestrians are not bound by the oneways of vehicles
+    if matches!(travel_type, TravelType::Pedestrian | TravelType::Wheelchair) {
+        return get_value("oneway:foot").unwrap_or((true, true));
+    }
+    let implied = match tags.get("junction").map(|s| s.trim()) {
+        Some("roundabout") | Some("circular") => Some((true, false)),
+        _ => match tags.get("highway").map(|s| s.trim()) {
+            Some("motorway") | Some("motorway_link") => Some((true, false)),
+            _ => None,
+        },
+    };
+    let directions = get_value("oneway").or(implied).unwrap_or((true, true));
+    if travel_type != TravelType::Bicycle {
+        return directions;
+    }
+    if let Some(directions) = get_value("oneway:bicycle") {
+        return directions;
+    }
+    let contraflow = [
+        "cycleway",
+        "cycleway:left",
+        "cycleway:right",
+        "cycleway:both",
+    ]
+    .iter()
+    .filter_map(|key| tags.get(*key))
+    .any(|v| v.trim().starts_with("opposite"));
+    if contraflow && directions != (false, false) {
+        (true, true)
+    } else {
+        directions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(pairs: &[(&str, &str)]) -> Tags {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string().into(), v.to_string().into()))
+            .collect()
+    }
+
+    #[test]
+    fn duration() {
+        assert_eq!(parse_duration("45"), Some(2700));
+        assert_eq!(parse_duration("1:30"), Some(5400));
+        assert_eq!(parse_duration("01:30:15"), Some(5415));
+        assert_eq!(parse_duration("PT1H30M"), Some(5400));
+        assert_eq!(parse_duration("PT0,5H"), Some(1800));
+        assert_eq!(parse_duration("P1DT2H"), Some(93600));
+        assert_eq!(parse_duration("0:00"), None);
+        assert_eq!(parse_duration("P1H"), None);
+        assert_eq!(parse_duration("PT1"), None);
+        assert_eq!(parse_duration("about an hour"), None);
+        assert!(is_ferry(&tags(&[("route", "ferry")])));
+        assert!(is_ferry(&tags(&[("route", "shuttle_train")])));
+        assert!(!is_ferry(&tags(&[("route", "bus")])));
+    }
+
+    #[test]
+    fn access_hierarchy() {
+        let way = tags(&[("highway", "track"), ("access", "no"), ("bicycle", "yes")]);
+        assert_eq!(
+            parse_access(&way, TravelType::Car),
+            Some((Access::No, "access"))
+        );
+        assert_eq!(
+            parse_access(&way, TravelType::Bicycle),
+            Some((Access::Yes, "bicycle"))
+        );
+        assert_eq!(
+            parse_access(&way, TravelType::Pedestrian),
+            Some((Access::No, "access"))
+        );
+
+        let way = tags(&[("vehicle", "destination"), ("motor_vehicle", "private")]);
+        assert_eq!(
+            parse_access(&way, TravelType::Car),
+            Some((Access::No, "motor_vehicle"))
+        );
+        assert_eq!(
+            parse_access(&way, TravelType::Bicycle),
+            Some((Access::Destination, "vehicle"))
+        );
+        assert_eq!(parse_access(&way, TravelType::Pedestrian), None);
+    }
+
+    #[test]
+    fn access_unknown_values() {
+        // unknown values fall back to the more general key
+        let way = tags(&[("access", "no"), ("motorcar", "unknown")]);
+        assert_eq!(
+            parse_access(&way, TravelType::Car),
+            Some((Access::No, "access"))
+        );
+        let way = tags(&[("foot", "maybe")]);
+        assert_eq!(parse_access(&way, TravelType::Pedestrian), None);
+        let way = tags(&[("foot", " designated ")]);
+        assert_eq!(
+            parse_access(&way, TravelType::Pedestrian),
+            Some((Access::Yes, "foot"))
+        );
+    }
+
+    #[test]
+    fn max_speed_numeric() {
+        assert_eq!(parse_max_speed("50"), Some(50));
+        assert_eq!(parse_max_speed("30 km/h"), Some(30));
+        assert_eq!(parse_max_speed("40 mph"), Some(64));
+        assert_eq!(parse_max_speed("20mph"), Some(32));
+        assert_eq!(parse_max_speed("10 knots"), Some(19));
+        assert_eq!(parse_max_speed("7.5"), Some(8));
+        assert_eq!(parse_max_speed("maxspeed=50"), Some(50));
+        assert_eq!(parse_max_speed("0"), None);
+        assert_eq!(parse_max_speed("50 furlongs"), None);
+        assert_eq!(parse_max_speed("fast"), None);
+    }
+
+    #[test]
+    fn max_speed_special() {
+        assert_eq!(parse_max_speed("none"), None);
+        assert_eq!(parse_max_speed("signals"), None);
+        assert_eq!(parse_max_speed("walk"), Some(WALK_SPEED));
+        assert_eq!(parse_max_speed("50;30"), Some(30));
+        assert_eq!(parse_max_speed("50;"), Some(50));
+        assert_eq!(parse_max_speed("none;80"), Some(80));
+    }
+
+    #[test]
+    fn max_speed_implicit() {
+        assert_eq!(parse_max_speed("DE:urban"), Some(50));
+        assert_eq!(parse_max_speed("DE:rural"), Some(100));
+        assert_eq!(parse_max_speed("FR:rural"), Some(80));
+        assert_eq!(parse_max_speed("PL:motorway"), Some(140));
+        assert_eq!(parse_max_speed("RU:urban"), Some(60));
+        assert_eq!(parse_max_speed("GB:nsl_single"), Some(97));
+        assert_eq!(parse_max_speed("DE:zone30"), Some(30));
+        assert_eq!(parse_max_speed("DE:zone:30"), Some(30));
+        assert_eq!(parse_max_speed("zone:maxspeed=de:30"), Some(30));
+        assert_eq!(parse_max_speed("DE:living_street"), Some(WALK_SPEED));
+        // countries without an entry use general defaults
+        assert_eq!(parse_max_speed("XY:urban"), Some(50));
+        assert_eq!(parse_max_speed("DE:unknown"), None);
+    }
+
+    #[test]
+    fn one_way() {
+        let way = tags(&[("highway", "primary"), ("oneway", "true")]);
+        assert_eq!(parse_one_way(&way, TravelType::Car), (true, false));
+        assert_eq!(parse_one_way(&way, TravelType::Bicycle), (true, false));
+        assert_eq!(parse_one_way(&way, TravelType::Pedestrian), (true, true));
+
+        let way = tags(&[("oneway", "-1")]);
+        assert_eq!(parse_one_way(&way, TravelType::Car), (false, true));
+        let way = tags(&[("oneway", "reversible")]);
+        assert_eq!(parse_one_way(&way, TravelType::Car), (false, false));
+        let way = tags(&[("oneway", "unknown")]);
+        assert_eq!(parse_one_way(&way, TravelType::Car), (true, true));
+    }
+
+    #[test]
+    fn one_way_implied() {
+        let way = tags(&[("highway", "motorway")]);
+        assert_eq!(parse_one_way(&way, TravelType::Car), (true, false));
+        let way = tags(&[("highway", "motorway"), ("oneway", "no")]);
+        assert_eq!(parse_one_way(&way, TravelType::Car), (true, true));
+        let way = tags(&[("highway", "motorway_link")]);
+        assert_eq!(parse_one_way(&way, TravelType::Car), (true, false));
+        let way = tags(&[("highway", "motorway_link"), ("oneway", "-1")]);
+        assert_eq!(parse_one_way(&way, TravelType::Car), (false, true));
+        let way = tags(&[("highway", "residential"), ("junction", "roundabout")]);
+        assert_eq!(parse_one_way(&way, TravelType::Car), (true, false));
+        assert_eq!(parse_one_way(&way, TravelType::Bicycle), (true, false));
+        assert_eq!(parse_one_way(&way, TravelType::Pedestrian), (true, true));
+    }
+
+    #[test]
+    fn one_way_bicycle() {
+        let way = tags(&[("oneway", "yes"), ("oneway:bicycle", "no")]);
+        assert_eq!(parse_one_way(&way, TravelType::Car), (true, false));
+        assert_eq!(parse_one_way(&way, TravelType::Bicycle), (true, true));
+
+        let way = tags(&[("oneway", "yes"), ("cycleway:left", "opposite_lane")]);
+        assert_eq!(parse_one_way(&way, TravelType::Car), (true, false));
+        assert_eq!(parse_one_way(&way, TravelType::Bicycle), (true, true));
+
+        let way = tags(&[("oneway:bicycle", "yes")]);
+        assert_eq!(parse_one_way(&way, TravelType::Car), (true, true));
+        assert_eq!(parse_one_way(&way, TravelType::Bicycle), (true, false));
+
+        let way = tags(&[("oneway:foot", "-1")]);
+        assert_eq!(parse_one_way(&way, TravelType::Pedestrian), (false, true));
+    }
+
+    #[test]
+    fn surface() {
+        assert_eq!(parse_surface(&tags(&[("highway", "residential")])), 100);
+        assert_eq!(parse_surface(&tags(&[("surface", "gravel")])), 70);
+        assert_eq!(
+            parse_surface(&tags(&[("tracktype", "grade5"), ("surface", "gravel")])),
+            40
+        );
+        assert_eq!(
+            parse_surface(&tags(&[("surface", "asphalt"), ("smoothness", "bad")])),
+            70
+        );
+        assert_eq!(parse_surface(&tags(&[("surface", "unknown")])), 100);
+
+        assert!(is_unpaved(&tags(&[("surface", "dirt")])));
+        assert!(is_unpaved(&tags(&[("tracktype", "grade3")])));
+        assert!(is_unpaved(&tags(&[("surface", "compacted")])));
+        assert!(!is_unpaved(&tags(&[("surface", "paving_stones")])));
+        assert!(!is_unpaved(&tags(&[("surface", "sett")])));
+        assert!(!is_unpaved(&tags(&[("surface", "cobblestone")])));
+        assert!(!is_unpaved(&tags(&[("surface", "wood")])));
+        assert!(!is_unpaved(&tags(&[("highway", "track")])));
+
+        assert!(has_cycleway(&tags(&[("cycleway:right", "track")])));
+        assert!(has_cycleway(&tags(&[("bicycle", "designated")])));
+        assert!(!has_cycleway(&tags(&[("cycleway", "no")])));
+    }
+
+    #[test]
+    fn dimensions() {
+        assert_eq!(parse_weight("7.5"), Some(7.5));
+        assert_eq!(parse_weight("3.5 t"), Some(3.5));
+        assert_eq!(parse_weight("7500 kg"), Some(7.5));
+        assert_eq!(parse_weight("none"), None);
+        assert_eq!(parse_length("3.5"), Some(3.5));
+        assert_eq!(parse_length("4 m"), Some(4.0));
+        assert!((parse_length("12'6\"").unwrap() - 3.81).abs() < 0.001);
+        assert!((parse_length("10 ft").unwrap() - 3.048).abs() < 0.001);
+        assert_eq!(parse_length("default"), None);
+        assert_eq!(parse_length("below_default"), None);
+    }
+
+    #[test]
+    fn incline() {
+        assert_eq!(parse_incline("10%"), Some(10.0));
+        assert_eq!(parse_incline("-6 %"), Some(6.0));
+        assert_eq!(parse_incline("-4.5"), Some(4.5));
+        assert!((parse_incline("45°").unwrap() - 100.0).abs() < 0.01);
+        assert_eq!(parse_incline("up"), None);
+    }
+
+    #[test]
+    fn street_types() {
+        let wheelchair = |highway: &str, sidewalk: bool| {
+            osm_pbf::is_sub_travel_type(get_street_type(highway, sidewalk), TravelType::Wheelchair)
+        };
+        let pedestrian = |highway: &str| {
+            osm_pbf::is_sub_travel_type(get_street_type(highway, false), TravelType::Pedestrian)
+        };
+        assert!(wheelchair("footway", false) && pedestrian("footway"));
+        assert!(wheelchair("elevator", false) && pedestrian("elevator"));
+        assert!(!wheelchair("steps", false) && pedestrian("steps"));
+        assert!(!wheelchair("primary", false) && wheelchair("primary", true));
+        assert!(wheelchair("residential", false));
+        assert!(!wheelchair("motorway", true));
+    }
+
+    #[test]
+    fn sidewalk() {
+        assert!(has_sidewalk(&tags(&[("sidewalk", "both")])));
+        assert!(!has_sidewalk(&tags(&[("sidewalk", "no")])));
+        assert!(!has_sidewalk(&tags(&[("highway", "primary")])));
     }
 }
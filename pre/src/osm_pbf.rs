@@ -1,105 +1,333 @@
 use super::*;
-use osmpbfreader::{groups, primitive_block_from_blob, OsmPbfReader};
-use std::collections::hash_map::Entry;
+use osmpbfreader::fileformat::Blob;
+use osmpbfreader::osmformat::PrimitiveBlock;
+use osmpbfreader::{groups, primitive_block_from_blob, OsmId, OsmObj, OsmPbfReader};
 use std::fs::File;
 use std::path::Path;
 
-pub fn get_pbf(filename: &str) -> osmpbfreader::OsmPbfReader<std::fs::File> {
+/// osm-data from a pbf-file or an already parsed xml-file
+pub enum OsmInput {
+    Pbf(OsmPbfReader<File>),
+    Xml(Vec<OsmObj>),
+}
+
+/// part of the input, which is processed at once
+enum Block<'a> {
+    Pbf(&'a PrimitiveBlock),
+    Xml(&'a [OsmObj]),
+}
+
+impl Block<'_> {
+    fn nodes(&self) -> Box<dyn Iterator<Item = osmpbfreader::Node> + '_> {
+        match self {
+            Block::Pbf(block) => Box::new(
+                block
+                    .primitivegroup
+                    .iter()
+                    .flat_map(move |group| groups::nodes(group, block)),
+            ),
+            Block::Xml(objects) => Box::new(objects.iter().filter_map(|o| o.node().cloned())),
+        }
+    }
+
+    fn ways(&self) -> Box<dyn Iterator<Item = osmpbfreader::Way> + '_> {
+        match self {
+            Block::Pbf(block) => Box::new(
+                block
+                    .primitivegroup
+                    .iter()
+                    .flat_map(move |group| groups::ways(group, block)),
+            ),
+            Block::Xml(objects) => Box::new(objects.iter().filter_map(|o| o.way().cloned())),
+        }
+    }
+
+    fn relations(&self) -> Box<dyn Iterator<Item = osmpbfreader::Relation> + '_> {
+        match self {
+            Block::Pbf(block) => Box::new(
+                block
+                    .primitivegroup
+                    .iter()
+                    .flat_map(move |group| groups::relations(group, block)),
+            ),
+            Block::Xml(objects) => Box::new(objects.iter().filter_map(|o| o.relation().cloned())),
+        }
+    }
+}
+
+/// open the input, files ending with .osm are read as xml and all others as pbf
+pub fn get_input(filename: &str) -> OsmInput {
+    if filename.ends_with(".osm") {
+        return OsmInput::Xml(osm_xml::read_xml(filename));
+    }
     let path = Path::new(&filename);
     if !path.exists() {
         println!("{} not found", filename);
         std::process::exit(1);
     }
     let r = File::open(path).unwrap();
-    OsmPbfReader::new(r)
+    OsmInput::Pbf(OsmPbfReader::new(r))
 }
 
-/// store all way-IDs that are having the "highway" tag. with speed-limit
-pub fn read_edges(
-    pbf: &mut osmpbfreader::OsmPbfReader<std::fs::File>,
-    full_edges: &mut Vec<OsmWay>,
-    osm_id_mapping: &mut HashMap<i64, usize>,
+/// decode the blobs on the rayon pool and process each block in parallel.
+/// the results are merged in the order of the blobs, so the output is deterministic.
+/// xml-input is processed as a single block
+fn process_blocks<T, P, M>(input: &mut OsmInput, process: P, mut merge: M)
+where
+    T: Send,
+    P: Fn(&Block) -> T + Sync,
+    M: FnMut(T),
+{
+    let pbf = match input {
+        OsmInput::Pbf(pbf) => pbf,
+        OsmInput::Xml(objects) => {
+            merge(process(&Block::Xml(objects)));
+            return;
+        }
+    };
+    let batch_size = rayon::current_num_threads() * BLOBS_PER_THREAD;
+    let mut blobs = pbf.blobs();
+    loop {
+        let batch: Vec<Blob> = blobs
+            .by_ref()
+            .take(batch_size)
+            .map(|b| b.unwrap())
+            .collect();
+        if batch.is_empty() {
+            break;
+        }
+        let results: Vec<T> = batch
+            .par_iter()
+            .map(|blob| process(&Block::Pbf(&primitive_block_from_blob(blob).unwrap())))
+            .collect();
+        results.into_iter().for_each(&mut merge);
+    }
+}
+
+/// store all restriction relations and prepare the storage of their ways
+pub fn read_restrictions(
+    input: &mut OsmInput,
+    restrictions: &mut Vec<OsmRestriction>,
+    way_nodes: &mut HashMap<i64, Vec<NodeId>>,
 ) {
-    let mut amount_nodes = 0;
-    for block in pbf.blobs().map(|b| primitive_block_from_blob(&b.unwrap())) {
-        let block = block.unwrap();
-        for group in block.primitivegroup.iter() {
-            for way in groups::ways(group, &block) {
-                if way.tags.contains_key("highway") {
-                    let highway = way.tags.get("highway").unwrap().trim();
-                    let mut has_sidewalk: bool = false;
-                    if way.tags.contains_key("sidewalk") {
-                        has_sidewalk = !matches!(
-                            way.tags.get("sidewalk").unwrap().trim(),
-                            "None" | "none" | "No" | "no"
-                        )
-                    }
-                    let travel_type = osm_parsing::get_street_type(highway, has_sidewalk);
-                    if !is_sub_travel_type(travel_type) {
-                        continue;
-                    }
-                    let mut max_speed: &str = "";
-                    if way.tags.contains_key("maxspeed") {
-                        max_speed = way.tags.get("maxspeed").unwrap().trim();
+    process_blocks(
+        input,
+        |block| {
+            let mut result = Vec::<OsmRestriction>::new();
+            for relation in block.relations() {
+                if !relation.tags.contains("type", "restriction") {
+                    continue;
+                }
+                let mut from: Option<i64> = None;
+                let mut to: Option<i64> = None;
+                let mut via_node: Option<i64> = None;
+                let mut via_ways = Vec::<i64>::new();
+                for member in relation.refs.iter() {
+                    match (member.role.as_str(), member.member) {
+                        ("from", OsmId::Way(id)) => from = Some(id.0),
+                        ("to", OsmId::Way(id)) => to = Some(id.0),
+                        ("via", OsmId::Node(id)) => via_node = Some(id.0),
+                        ("via", OsmId::Way(id)) => via_ways.push(id.0),
+                        _ => (),
                     }
-                    let speed = osm_parsing::parse_speed(max_speed, highway);
-                    let mut one_way: &str = "";
-                    if way.tags.contains_key("oneway") {
-                        one_way = way.tags.get("oneway").unwrap().trim();
+                }
+                // exactly one via-node or at least one via-way is needed
+                if let (Some(from), Some(to)) = (from, to) {
+                    if via_node.is_some() == via_ways.is_empty() {
+                        result.push(OsmRestriction {
+                            from,
+                            via_node,
+                            via_ways,
+                            to,
+                            tags: relation.tags,
+                        });
                     }
-                    let (one_way, reverse_dir): (bool, bool) = osm_parsing::parse_one_way(one_way);
-                    // get all node IDs from ways without duplication
-                    let mut prev_id: usize;
-                    let osm_id = way.nodes[0].0;
-                    prev_id = match osm_id_mapping.entry(osm_id) {
-                        Entry::Occupied(o) => *o.into_mut(),
-                        Entry::Vacant(v) => {
-                            amount_nodes += 1;
-                            *v.insert(amount_nodes - 1)
-                        }
+                }
+            }
+            result
+        },
+        |block_restrictions| {
+            for restriction in block_restrictions {
+                let way_ids = std::iter::once(&restriction.from)
+                    .chain(&restriction.via_ways)
+                    .chain(Some(&restriction.to));
+                for way_id in way_ids {
+                    way_nodes.insert(*way_id, Vec::new());
+                }
+                restrictions.push(restriction);
+            }
+        },
+    );
+    reset_input(input);
+}
+
+/// store all way-IDs that are having the "highway" tag or are ferries. with speed-limit
+/// (one list of edges per given profile, the nodes of all ways in way_nodes are stored,
+/// the information about every used way is stored in ways)
+pub fn read_edges(
+    input: &mut OsmInput,
+    profiles: &[Profile],
+    full_edges: &mut [Vec<OsmWay>],
+    osm_id_mapping: &mut OsmIdMapping,
+    way_nodes: &mut HashMap<i64, Vec<NodeId>>,
+    ways: &mut Vec<WayInfo>,
+    strings: &mut StringTable,
+) {
+    process_blocks(
+        input,
+        |block| {
+            let mut result = Vec::new();
+            for way in block.ways() {
+                let ferry = osm_parsing::is_ferry(&way.tags);
+                if !way.tags.contains_key("highway") && !ferry {
+                    continue;
+                }
+                // speed, penalty, speed factor and directions of every profile using this way
+                let matching_profiles: Vec<_> = profiles
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, profile)| {
+                        let (forward_speed, backward_speed, penalty) =
+                            profile.get_way_settings(&way.tags)?;
+                        let (speed_factor, surface_penalty) =
+                            profile.get_surface_settings(&way.tags)?;
+                        let settings = (forward_speed, backward_speed, penalty + surface_penalty);
+                        let directions = osm_parsing::parse_one_way(&way.tags, profile.travel_type);
+                        Some((i, settings, speed_factor, directions))
+                    })
+                    .filter(|(_, _, _, (forward, backward))| *forward || *backward)
+                    .collect();
+                if !matching_profiles.is_empty() {
+                    let get_tag = |key: &str| way.tags.get(key).map_or("", |s| s.trim());
+                    // ferries are named by their route
+                    let class = if ferry { "route" } else { "highway" };
+                    let names =
+                        [get_tag("name"), get_tag("ref"), get_tag(class)].map(|s| s.to_string());
+                    let duration = match ferry {
+                        true => way
+                            .tags
+                            .get("duration")
+                            .and_then(|s| osm_parsing::parse_duration(s)),
+                        false => None,
                     };
-                    // iterate over nodes and add them
-                    for node in way.nodes.iter().skip(1) {
-                        let osm_id = node.0;
-                        let id = match osm_id_mapping.entry(osm_id) {
-                            Entry::Occupied(o) => *o.into_mut(),
-                            Entry::Vacant(v) => {
-                                amount_nodes += 1;
-                                *v.insert(amount_nodes - 1)
-                            }
-                        };
-                        if !reverse_dir || !one_way {
-                            full_edges.push(OsmWay {
+                    result.push((
+                        way.id.0,
+                        way.nodes,
+                        matching_profiles,
+                        names,
+                        ferry,
+                        duration,
+                    ));
+                }
+            }
+            result
+        },
+        |block_ways| {
+            for (
+                way_id,
+                osm_nodes,
+                matching_profiles,
+                [name, reference, highway],
+                ferry,
+                duration,
+            ) in block_ways
+            {
+                let way = ways.len();
+                ways.push(WayInfo {
+                    osm_id: way_id,
+                    name: strings.get_id(&name),
+                    reference: strings.get_id(&reference),
+                    highway: strings.get_id(&highway),
+                    ferry,
+                    duration,
+                });
+                // the edges store the osm-ids until the mapping is finished
+                let ids: Vec<NodeId> = osm_nodes
+                    .iter()
+                    .map(|node| {
+                        osm_id_mapping.insert(node.0);
+                        node.0 as NodeId
+                    })
+                    .collect();
+                // iterate over nodes and add them
+                for pair in ids.windows(2) {
+                    let (prev_id, id) = (pair[0], pair[1]);
+                    for (
+                        i,
+                        (forward_speed, backward_speed, penalty),
+                        speed_factor,
+                        (forward, backward),
+                    ) in &matching_profiles
+                    {
+                        if *forward {
+                            full_edges[*i].push(OsmWay {
                                 source: prev_id,
                                 target: id,
-                                speed,
+                                speed: *forward_speed,
                                 distance: 0,
+                                penalty: *penalty,
+                                way,
+                                ascent: 0,
+                                descent: 0,
+                                ferry,
+                                delay: 0,
+                                speed_factor: *speed_factor,
+                                duration: None,
                             });
                         }
-                        if reverse_dir || !one_way {
-                            full_edges.push(OsmWay {
+                        if *backward {
+                            full_edges[*i].push(OsmWay {
                                 source: id,
                                 target: prev_id,
-                                speed,
+                                speed: *backward_speed,
                                 distance: 0,
+                                penalty: *penalty,
+                                way,
+                                ascent: 0,
+                                descent: 0,
+                                ferry,
+                                delay: 0,
+                                speed_factor: *speed_factor,
+                                duration: None,
                             });
                         }
-                        prev_id = id;
                     }
                 }
+                // remember nodes of restricted ways
+                if let Some(stored_nodes) = way_nodes.get_mut(&way_id) {
+                    *stored_nodes = ids;
+                }
             }
-        }
+        },
+    );
+
+    // replace the osm-ids by own ids
+    osm_id_mapping.finish();
+    let get_id = |osm_id: NodeId| osm_id_mapping.get(osm_id as i64).unwrap();
+    for profile_edges in full_edges.iter_mut() {
+        profile_edges.par_iter_mut().for_each(|edge| {
+            edge.source = get_id(edge.source);
+            edge.target = get_id(edge.target);
+        });
+    }
+    for stored_nodes in way_nodes.values_mut() {
+        stored_nodes
+            .iter_mut()
+            .for_each(|node| *node = get_id(*node));
     }
 }
 
 /// store all geo-information about nodes
+/// and the tags of nodes with a barrier or highway tag, e.g. traffic signals.
+/// tags are mostly set on simple nodes, so they are read besides the dense nodes
 pub fn read_ways(
-    pbf: &mut osmpbfreader::OsmPbfReader<std::fs::File>,
+    input: &mut OsmInput,
     nodes: &mut Vec<Node>,
-    osm_id_mapping: &mut HashMap<i64, usize>,
+    node_tags: &mut HashMap<NodeId, Tags>,
+    osm_id_mapping: &OsmIdMapping,
 ) {
-    // reset pbf reader
-    osm_pbf::reset_pbf(pbf);
+    // reset reader
+    reset_input(input);
 
     // resize nodes
     nodes.resize(
@@ -110,53 +338,75 @@ pub fn read_ways(
             rank: INVALID_RANK,
         },
     );
-    for block in pbf.blobs().map(|b| primitive_block_from_blob(&b.unwrap())) {
-        let block = block.unwrap();
-        for group in block.primitivegroup.iter() {
-            for node in groups::dense_nodes(group, &block) {
+    process_blocks(
+        input,
+        |block| {
+            let mut result = Vec::<(NodeId, Node, Option<Tags>)>::new();
+            for node in block.nodes() {
                 // check if node in osm_id_mapping
-                match osm_id_mapping.get(&node.id.0) {
-                    Some(our_id) => {
-                        let latitude = node.decimicro_lat as f32 / 10_000_000.0;
-                        let longitude = node.decimicro_lon as f32 / 10_000_000.0;
-                        nodes[*our_id] = Node {
+                if let Some(our_id) = osm_id_mapping.get(node.id.0) {
+                    let latitude = node.decimicro_lat as f32 / 10_000_000.0;
+                    let longitude = node.decimicro_lon as f32 / 10_000_000.0;
+                    let tags = Some(node.tags)
+                        .filter(|t| t.contains_key("barrier") || t.contains_key("highway"));
+                    result.push((
+                        our_id,
+                        Node {
                             latitude,
                             longitude,
                             rank: INVALID_RANK,
-                        };
-                    }
-                    None => continue,
+                        },
+                        tags,
+                    ));
                 }
             }
-        }
-    }
+            result
+        },
+        |block_nodes| {
+            for (our_id, node, tags) in block_nodes {
+                nodes[our_id] = node;
+                if let Some(tags) = tags {
+                    node_tags.insert(our_id, tags);
+                }
+            }
+        },
+    );
 }
 
 /// rewinds the reader back to the beginning of the file
-pub fn reset_pbf(pbf: &mut osmpbfreader::OsmPbfReader<std::fs::File>) {
-    match pbf.rewind() {
-        Ok(_ok) => (),
-        Err(_e) => panic!("rewind was not successfull"),
+pub fn reset_input(input: &mut OsmInput) {
+    if let OsmInput::Pbf(pbf) = input {
+        match pbf.rewind() {
+            Ok(_ok) => (),
+            Err(_e) => panic!("rewind was not successfull"),
+        }
     }
 }
 
-/// check if the travel type matches the given one
-pub fn is_sub_travel_type(travel_type: TravelType) -> bool {
-    match TRAVEL_TYPE {
-        TravelType::Car => matches!(
-            travel_type,
+/// check if the street type can be used with the given travel type
+pub fn is_sub_travel_type(street_type: TravelType, travel_type: TravelType) -> bool {
+    match travel_type {
+        TravelType::Car | TravelType::Hgv => matches!(
+            street_type,
             TravelType::Car | TravelType::CarBicycle | TravelType::All
         ),
         TravelType::Bicycle => matches!(
-            travel_type,
+            street_type,
             TravelType::CarBicycle
                 | TravelType::Bicycle
                 | TravelType::BicyclePedestrian
                 | TravelType::All
         ),
         TravelType::Pedestrian => matches!(
-            travel_type,
-            TravelType::BicyclePedestrian | TravelType::Pedestrian | TravelType::All
+            street_type,
+            TravelType::BicyclePedestrian
+                | TravelType::Pedestrian
+                | TravelType::Wheelchair
+                | TravelType::All
+        ),
+        TravelType::Wheelchair => matches!(
+            street_type,
+            TravelType::BicyclePedestrian | TravelType::Wheelchair | TravelType::All
         ),
         TravelType::All => true,
         _ => panic!("Invalid TravelType is set"),
@@ -0,0 +1,274 @@
+use super::*;
+use osmpbfreader::WayId as OsmWayId;
+use osmpbfreader::{NodeId as OsmNodeId, OsmId, OsmObj, Ref, Relation, RelationId};
+use std::path::Path;
+
+/// a start or empty element with its attributes
+struct Element<'a> {
+    name: &'a str,
+    attributes: Vec<(&'a str, String)>,
+    is_empty: bool,
+}
+
+impl Element<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn get_id(&self, key: &str) -> Result<i64, String> {
+        let value = self
+            .get(key)
+            .ok_or_else(|| format!("{} without {}", self.name, key))?;
+        value
+            .parse()
+            .map_err(|_| format!("invalid {} {} of {}", key, value, self.name))
+    }
+}
+
+/// replace the predefined and numeric entities
+fn unescape(s: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find(';')
+            .ok_or_else(|| format!("unterminated entity in {}", s))?;
+        let entity = &rest[start + 1..start + end];
+        let c = match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = match entity.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                    None => entity.strip_prefix('#').and_then(|dec| dec.parse().ok()),
+                };
+                code.and_then(char::from_u32)
+                    .ok_or_else(|| format!("unknown entity &{};", entity))?
+            }
+        };
+        result.push(c);
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// parse the inside of a start or empty element, e.g. `node id="1" lat="48.1" lon="9.1"/`
+fn parse_element(s: &str) -> Result<Element<'_>, String> {
+    let is_empty = s.ends_with('/');
+    let s = s.trim_end_matches('/');
+    let name_end = s.find(char::is_whitespace).unwrap_or(s.len());
+    let name = &s[..name_end];
+    let mut attributes = Vec::new();
+    let mut rest = s[name_end..].trim_start();
+    while !rest.is_empty() {
+        let equals = rest
+            .find('=')
+            .ok_or_else(|| format!("invalid attribute in <{}>", s))?;
+        let key = rest[..equals].trim();
+        let value = rest[equals + 1..].trim_start();
+        let quote = value
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')
+            .ok_or_else(|| format!("unquoted attribute {} in <{}>", key, s))?;
+        let end = value[1..]
+            .find(quote)
+            .ok_or_else(|| format!("unterminated attribute {} in <{}>", key, s))?;
+        attributes.push((key, unescape(&value[1..end + 1])?));
+        rest = value[end + 2..].trim_start();
+    }
+    Ok(Element {
+        name,
+        attributes,
+        is_empty,
+    })
+}
+
+/// convert a coordinate to decimicro degrees
+fn parse_coordinate(element: &Element, key: &str) -> Result<i32, String> {
+    let value = element
+        .get(key)
+        .ok_or_else(|| format!("node without {}", key))?;
+    let degrees: f64 = value
+        .parse()
+        .map_err(|_| format!("invalid {} {}", key, value))?;
+    Ok((degrees * 10_000_000.0).round() as i32)
+}
+
+/// parse the nodes, ways and relations of an osm xml-file.
+/// objects deleted in JOSM or not visible anymore are skipped
+// info from: https://wiki.openstreetmap.org/wiki/OSM_XML
+pub fn parse_xml(content: &str) -> Result<Vec<OsmObj>, String> {
+    let mut objects = Vec::<OsmObj>::new();
+    let mut current: Option<OsmObj> = None;
+    let mut skip_current = false;
+    let mut rest = content;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        // declarations, comments and closing elements
+        if rest.starts_with("<?") || rest.starts_with("<!") {
+            let end = match rest.starts_with("<!--") {
+                true => rest.find("-->").map(|end| end + 3),
+                false => rest.find('>').map(|end| end + 1),
+            };
+            rest = &rest[end.ok_or("unterminated declaration")?..];
+            continue;
+        }
+        let end = rest.find('>').ok_or("unterminated element")?;
+        let inner = rest[1..end].trim();
+        rest = &rest[end + 1..];
+        if let Some(name) = inner.strip_prefix('/') {
+            if matches!(name.trim(), "node" | "way" | "relation") {
+                if let Some(object) = current.take() {
+                    if !skip_current {
+                        objects.push(object);
+                    }
+                }
+            }
+            continue;
+        }
+
+        let element = parse_element(inner)?;
+        match element.name {
+            "node" | "way" | "relation" => {
+                skip_current = element.get("action") == Some("delete")
+                    || element.get("visible") == Some("false");
+                let id = element.get_id("id")?;
+                let object = match element.name {
+                    "node" => OsmObj::Node(osmpbfreader::Node {
+                        id: OsmNodeId(id),
+                        tags: Tags::new(),
+                        decimicro_lat: parse_coordinate(&element, "lat")?,
+                        decimicro_lon: parse_coordinate(&element, "lon")?,
+                    }),
+                    "way" => OsmObj::Way(osmpbfreader::Way {
+                        id: OsmWayId(id),
+                        tags: Tags::new(),
+                        nodes: Vec::new(),
+                    }),
+                    _ => OsmObj::Relation(Relation {
+                        id: RelationId(id),
+                        tags: Tags::new(),
+                        refs: Vec::new(),
+                    }),
+                };
+                if element.is_empty {
+                    if !skip_current {
+                        objects.push(object);
+                    }
+                } else {
+                    current = Some(object);
+                }
+            }
+            "tag" => {
+                let key = element.get("k").ok_or("tag without k")?;
+                let value = element.get("v").ok_or("tag without v")?;
+                let tags = match &mut current {
+                    Some(OsmObj::Node(node)) => &mut node.tags,
+                    Some(OsmObj::Way(way)) => &mut way.tags,
+                    Some(OsmObj::Relation(relation)) => &mut relation.tags,
+                    None => continue,
+                };
+                tags.insert(key.to_string().into(), value.to_string().into());
+            }
+            "nd" => {
+                if let Some(OsmObj::Way(way)) = &mut current {
+                    way.nodes.push(OsmNodeId(element.get_id("ref")?));
+                }
+            }
+            "member" => {
+                if let Some(OsmObj::Relation(relation)) = &mut current {
+                    let id = element.get_id("ref")?;
+                    let member = match element.get("type") {
+                        Some("node") => OsmId::Node(OsmNodeId(id)),
+                        Some("way") => OsmId::Way(OsmWayId(id)),
+                        Some("relation") => OsmId::Relation(RelationId(id)),
+                        _ => return Err(format!("invalid member type of relation {:?}", id)),
+                    };
+                    let role = element.get("role").unwrap_or("");
+                    relation.refs.push(Ref {
+                        member,
+                        role: role.to_string().into(),
+                    });
+                }
+            }
+            _ => (),
+        }
+    }
+    Ok(objects)
+}
+
+pub fn read_xml(filename: &str) -> Vec<OsmObj> {
+    let path = Path::new(&filename);
+    if !path.exists() {
+        println!("{} not found", filename);
+        std::process::exit(1);
+    }
+    let content = std::fs::read_to_string(path).unwrap();
+    match parse_xml(&content) {
+        Ok(objects) => objects,
+        Err(e) => {
+            println!("could not parse {}: {}", filename, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xml() {
+        let content = r#"<?xml version='1.0' encoding='UTF-8'?>
+            <osm version="0.6" generator="JOSM">
+              <!-- a comment with <tags> -->
+              <node id="1" lat="48.1" lon="9.2" />
+              <node id="2" lat='48.2' lon='9.3'>
+                <tag k="highway" v="traffic_signals"/>
+              </node>
+              <node id="-3" action="delete" lat="48.3" lon="9.4"/>
+              <way id="10">
+                <nd ref="1"/>
+                <nd ref="2"/>
+                <tag k="highway" v="residential"/>
+                <tag k="name" v="Fish &amp; Chips &#233;"/>
+              </way>
+              <relation id="20">
+                <member type="way" ref="10" role="from"/>
+                <member type="node" ref="2" role="via"/>
+                <tag k="type" v="restriction"/>
+              </relation>
+            </osm>"#;
+        let objects = parse_xml(content).unwrap();
+        assert_eq!(objects.len(), 4);
+
+        let node = objects[1].node().unwrap();
+        assert_eq!(node.id, OsmNodeId(2));
+        assert_eq!(
+            (node.decimicro_lat, node.decimicro_lon),
+            (482_000_000, 93_000_000)
+        );
+        assert!(node.tags.contains("highway", "traffic_signals"));
+
+        let way = objects[2].way().unwrap();
+        assert_eq!(way.nodes, vec![OsmNodeId(1), OsmNodeId(2)]);
+        assert!(way.tags.contains("name", "Fish & Chips é"));
+
+        let relation = objects[3].relation().unwrap();
+        assert_eq!(relation.refs.len(), 2);
+        assert_eq!(relation.refs[1].member, OsmId::Node(OsmNodeId(2)));
+        assert_eq!(relation.refs[1].role, "via");
+
+        assert!(parse_xml("<osm><node id=\"1\" lat=\"48.1\"/></osm>").is_err());
+        assert!(parse_xml("<osm><node id=\"1 lat=\"48.1\"/></osm>").is_err());
+    }
+}
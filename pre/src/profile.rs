@@ -0,0 +1,1109 @@
+use super::*;
+use osmpbfreader::Tags;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// all highway classes known to the built-in profiles
+// infos from https://wiki.openstreetmap.org/wiki/Key:highway
+const HIGHWAY_CLASSES: [&str; 36] = [
+    "motorway",
+    "motorway_link",
+    "trunk",
+    "trunk_link",
+    "raceway",
+    "services",
+    "rest_area",
+    "primary",
+    "primary_link",
+    "secondary",
+    "secondary_link",
+    "tertiary",
+    "tertiary_link",
+    "cycleway",
+    "trail",
+    "track",
+    "path",
+    "elevator",
+    "platform",
+    "corridor",
+    "bus_stop",
+    "bridleway",
+    "steps",
+    "pedestrian",
+    "footway",
+    "unclassified",
+    "residential",
+    "living_street",
+    "service",
+    "road",
+    "razed",
+    "abandoned",
+    "disused",
+    "construction",
+    "proposed",
+    "walk",
+];
+
+/// additional costs in percent of unpaved ways for the built-in car profile
+const UNPAVED_PENALTY: usize = 50;
+/// speed in percent on cycle infrastructure for the built-in bicycle profile
+const CYCLEWAY_FACTOR: usize = 110;
+/// speed of ferries without a duration in km/h
+const FERRY_SPEED: usize = 5;
+/// walking speed of the built-in pedestrian profile
+const PEDESTRIAN_SPEED: usize = 7;
+/// speed of the built-in wheelchair profile
+const WHEELCHAIR_SPEED: usize = 4;
+/// steepest incline in percent of the built-in wheelchair profile
+// info from: https://wiki.openstreetmap.org/wiki/Key:incline
+const WHEELCHAIR_MAX_INCLINE: usize = 8;
+/// maximum speed of the built-in hgv profile
+const HGV_SPEED: usize = 80;
+/// maximum speed of the built-in bicycle profile
+const BICYCLE_SPEED: usize = 20;
+
+fn default_true() -> bool {
+    true
+}
+
+/// how a single highway class is handled
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct HighwaySettings {
+    #[serde(default = "default_true")]
+    pub allowed: bool,
+    /// speed in km/h, used if there is no usable maxspeed tag
+    #[serde(default)]
+    pub speed: usize,
+    /// additional costs in percent
+    #[serde(default)]
+    pub penalty: usize,
+    /// the way can only be used if it has a sidewalk
+    #[serde(default)]
+    pub sidewalk_only: bool,
+}
+
+/// changes the handling of ways having a specific tag
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct TagOverride {
+    pub key: String,
+    /// matches every value if not set
+    #[serde(default)]
+    pub value: Option<String>,
+    /// allow or forbid the way. ways of unknown classes also need a speed
+    #[serde(default)]
+    pub allowed: Option<bool>,
+    #[serde(default)]
+    pub speed: Option<usize>,
+    #[serde(default)]
+    pub penalty: Option<usize>,
+}
+
+impl TagOverride {
+    fn new(key: &str, value: &str, allowed: Option<bool>, penalty: Option<usize>) -> Self {
+        TagOverride {
+            key: key.to_string(),
+            value: Some(value.to_string()),
+            allowed,
+            speed: None,
+            penalty,
+        }
+    }
+
+    fn matches(&self, tags: &Tags) -> bool {
+        match (tags.get(self.key.as_str()), &self.value) {
+            (Some(tag), Some(value)) => tag.trim() == value,
+            (Some(_), None) => true,
+            (None, _) => false,
+        }
+    }
+}
+
+/// how nodes with a barrier or a traffic control are passed
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct NodeSettings {
+    #[serde(default = "default_true")]
+    pub allowed: bool,
+    /// seconds added for passing the node (only when optimizing by time)
+    #[serde(default)]
+    pub penalty: usize,
+}
+
+impl NodeSettings {
+    fn new(allowed: bool, penalty: usize) -> Self {
+        NodeSettings { allowed, penalty }
+    }
+}
+
+/// dimensions of the vehicle, ways with lower limits are not used
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct VehicleSettings {
+    /// weight in tonnes
+    #[serde(default)]
+    pub weight: Option<f32>,
+    /// height in meters
+    #[serde(default)]
+    pub height: Option<f32>,
+    /// width in meters
+    #[serde(default)]
+    pub width: Option<f32>,
+    /// length in meters
+    #[serde(default)]
+    pub length: Option<f32>,
+}
+
+impl VehicleSettings {
+    /// check if the vehicle is within the limits of a way or node.
+    /// conditional limits are ignored
+    // info from: https://wiki.openstreetmap.org/wiki/Key:maxweight
+    pub fn fits(&self, tags: &Tags) -> bool {
+        let limit = |keys: &[&str], parse: fn(&str) -> Option<f32>| {
+            keys.iter()
+                .filter_map(|key| tags.get(*key).and_then(|s| parse(s)))
+                .reduce(f32::min)
+        };
+        let within = |value: Option<f32>, limit: Option<f32>| match (value, limit) {
+            (Some(value), Some(limit)) => value <= limit,
+            _ => true,
+        };
+        within(
+            self.weight,
+            limit(&["maxweight", "maxweight:hgv"], osm_parsing::parse_weight),
+        ) && within(
+            self.height,
+            limit(
+                &["maxheight", "maxheight:physical"],
+                osm_parsing::parse_length,
+            ),
+        ) && within(
+            self.width,
+            limit(
+                &["maxwidth", "maxwidth:physical"],
+                osm_parsing::parse_length,
+            ),
+        ) && within(
+            self.length,
+            limit(&["maxlength"], osm_parsing::parse_length),
+        )
+    }
+}
+
+/// handling of the surface of ways
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct SurfaceSettings {
+    /// reduce the speed according to surface, smoothness and tracktype
+    #[serde(default)]
+    pub speed_factors: bool,
+    /// speed in percent on ways with a cycle lane or track or designated for bicycles
+    #[serde(default = "default_cycleway_factor")]
+    pub cycleway_factor: usize,
+    /// additional costs in percent for unpaved ways
+    #[serde(default)]
+    pub unpaved_penalty: usize,
+    /// ways with a lower speed factor are not used
+    #[serde(default)]
+    pub min_factor: usize,
+}
+
+fn default_cycleway_factor() -> usize {
+    100
+}
+
+/// handling of ferries and car-shuttle trains
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct FerrySettings {
+    /// speed in km/h, used if there is no duration tag
+    pub speed: usize,
+    /// additional costs in percent
+    #[serde(default)]
+    pub penalty: usize,
+}
+
+/// additional time for climbing, if elevation data is given
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ElevationSettings {
+    /// seconds per 100 meters of ascent
+    #[serde(default)]
+    pub ascent: usize,
+    /// seconds per 100 meters of descent
+    #[serde(default)]
+    pub descent: usize,
+}
+
+/// turn handling, which needs an edge-based graph
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct TurnSettings {
+    /// respect restriction relations
+    #[serde(default = "default_true")]
+    pub restrictions: bool,
+    /// seconds added for turning by 180 degrees, scaled linearly with the turn angle
+    #[serde(default)]
+    pub max_turn_penalty: usize,
+    /// allow u-turns everywhere, not only at dead ends
+    #[serde(default)]
+    pub allow_u_turns: bool,
+}
+
+impl Default for TurnSettings {
+    fn default() -> Self {
+        TurnSettings {
+            restrictions: true,
+            max_turn_penalty: 0,
+            allow_u_turns: false,
+        }
+    }
+}
+
+/// declarative description how ways are used and weighted
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Profile {
+    /// used for selecting the profile and naming the output
+    pub name: String,
+    pub travel_type: TravelType,
+    /// known highway classes
+    pub highways: BTreeMap<String, HighwaySettings>,
+    /// settings for highway classes, which are not listed
+    #[serde(default)]
+    pub default_highway: Option<HighwaySettings>,
+    /// applied in the given order after the highway settings
+    #[serde(default)]
+    pub overrides: Vec<TagOverride>,
+    /// use the maxspeed tag instead of the speed of the highway class
+    #[serde(default = "default_true")]
+    pub use_maxspeed: bool,
+    /// speed cap in km/h
+    #[serde(default)]
+    pub max_speed: Option<usize>,
+    /// build an edge-based graph respecting turns
+    #[serde(default)]
+    pub turns: Option<TurnSettings>,
+    /// dimensions of the vehicle
+    #[serde(default)]
+    pub vehicle: Option<VehicleSettings>,
+    /// ways with a steeper incline tag in percent are not used
+    #[serde(default)]
+    pub max_incline: Option<usize>,
+    /// settings per barrier-value of nodes, unknown barriers can be passed.
+    /// kerbs can also be set by their kerb-value, e.g. "kerb=raised"
+    #[serde(default)]
+    pub barriers: BTreeMap<String, NodeSettings>,
+    /// settings per highway-value of nodes, e.g. traffic_signals
+    #[serde(default)]
+    pub traffic_controls: BTreeMap<String, NodeSettings>,
+    /// speed factors and penalties depending on the surface
+    #[serde(default)]
+    pub surfaces: Option<SurfaceSettings>,
+    /// use ferries and car-shuttle trains
+    #[serde(default)]
+    pub ferries: Option<FerrySettings>,
+    /// weight climbs when optimizing by time
+    #[serde(default)]
+    pub elevation: Option<ElevationSettings>,
+}
+
+impl Profile {
+    /// profile reproducing the hard-coded behaviour for the given travel type
+    pub fn builtin(travel_type: TravelType) -> Self {
+        let get_settings = |highway: &str| {
+            let speed = match travel_type {
+                TravelType::Pedestrian => PEDESTRIAN_SPEED,
+                TravelType::Wheelchair => WHEELCHAIR_SPEED,
+                TravelType::Hgv => osm_parsing::aproximate_speed_limit(highway).min(HGV_SPEED),
+                _ => osm_parsing::aproximate_speed_limit(highway),
+            };
+            let allowed = osm_pbf::is_sub_travel_type(
+                osm_parsing::get_street_type(highway, false),
+                travel_type,
+            );
+            let with_sidewalk = osm_pbf::is_sub_travel_type(
+                osm_parsing::get_street_type(highway, true),
+                travel_type,
+            );
+            HighwaySettings {
+                allowed: allowed || with_sidewalk,
+                speed,
+                penalty: 0,
+                sidewalk_only: !allowed && with_sidewalk,
+            }
+        };
+
+        let highways = HIGHWAY_CLASSES
+            .iter()
+            .map(|highway| (highway.to_string(), get_settings(highway)))
+            .collect();
+
+        // (value, allowed, penalty in seconds)
+        let barriers: &[(&str, bool, usize)] = match travel_type {
+            TravelType::Car | TravelType::Hgv => &[
+                ("bollard", false, 0),
+                ("gate", true, 30),
+                ("lift_gate", true, 15),
+            ],
+            TravelType::Bicycle => &[
+                ("bollard", true, 0),
+                ("gate", true, 10),
+                ("lift_gate", true, 5),
+            ],
+            TravelType::Pedestrian => &[("gate", true, 5)],
+            // info from: https://wiki.openstreetmap.org/wiki/Key:kerb
+            TravelType::Wheelchair => &[
+                ("gate", true, 10),
+                ("stile", false, 0),
+                ("turnstile", false, 0),
+                ("kissing_gate", false, 0),
+                ("cycle_barrier", false, 0),
+                ("kerb", true, 30),
+                ("kerb=raised", false, 0),
+                ("kerb=rolled", true, 10),
+                ("kerb=lowered", true, 0),
+                ("kerb=flush", true, 0),
+                ("kerb=no", true, 0),
+            ],
+            _ => &[],
+        };
+        let traffic_controls: &[(&str, bool, usize)] = match travel_type {
+            TravelType::Car | TravelType::Hgv | TravelType::Bicycle => &[
+                ("traffic_signals", true, 10),
+                ("stop", true, 5),
+                ("crossing", true, 2),
+            ],
+            TravelType::Pedestrian => &[("traffic_signals", true, 15), ("crossing", true, 5)],
+            TravelType::Wheelchair => &[
+                ("traffic_signals", true, 15),
+                ("crossing", true, 5),
+                ("elevator", true, 60),
+            ],
+            _ => &[],
+        };
+        let to_settings = |list: &[(&str, bool, usize)]| {
+            list.iter()
+                .map(|(value, allowed, penalty)| {
+                    (value.to_string(), NodeSettings::new(*allowed, *penalty))
+                })
+                .collect()
+        };
+
+        Profile {
+            name: travel_type.to_string(),
+            travel_type,
+            highways,
+            // unknown classes are handled like unknown strings
+            default_highway: Some(get_settings("")),
+            overrides: match travel_type {
+                // steps can not be used with foot=yes, only with a ramp
+                TravelType::Wheelchair => vec![
+                    TagOverride::new("highway", "steps", Some(false), None),
+                    TagOverride::new("wheelchair", "limited", None, Some(50)),
+                    TagOverride::new("ramp:wheelchair", "yes", Some(true), None),
+                ],
+                _ => Vec::new(),
+            },
+            use_maxspeed: !matches!(travel_type, TravelType::Pedestrian | TravelType::Wheelchair),
+            max_speed: match travel_type {
+                TravelType::Hgv => Some(HGV_SPEED),
+                TravelType::Bicycle => Some(BICYCLE_SPEED),
+                TravelType::Pedestrian => Some(PEDESTRIAN_SPEED),
+                TravelType::Wheelchair => Some(WHEELCHAIR_SPEED),
+                _ => None,
+            },
+            // articulated lorry with the maximum dimensions in the EU
+            vehicle: match travel_type {
+                TravelType::Hgv => Some(VehicleSettings {
+                    weight: Some(40.0),
+                    height: Some(4.0),
+                    width: Some(2.55),
+                    length: Some(16.5),
+                }),
+                _ => None,
+            },
+            max_incline: match travel_type {
+                TravelType::Wheelchair => Some(WHEELCHAIR_MAX_INCLINE),
+                _ => None,
+            },
+            turns: None,
+            barriers: to_settings(barriers),
+            traffic_controls: to_settings(traffic_controls),
+            surfaces: match travel_type {
+                TravelType::Car | TravelType::Hgv => Some(SurfaceSettings {
+                    speed_factors: false,
+                    cycleway_factor: 100,
+                    unpaved_penalty: UNPAVED_PENALTY,
+                    min_factor: 0,
+                }),
+                TravelType::Bicycle => Some(SurfaceSettings {
+                    speed_factors: true,
+                    cycleway_factor: CYCLEWAY_FACTOR,
+                    unpaved_penalty: 0,
+                    min_factor: 0,
+                }),
+                // rough surfaces can not be used at all
+                TravelType::Wheelchair => Some(SurfaceSettings {
+                    speed_factors: true,
+                    cycleway_factor: 100,
+                    unpaved_penalty: UNPAVED_PENALTY,
+                    min_factor: 50,
+                }),
+                _ => None,
+            },
+            ferries: Some(FerrySettings {
+                speed: FERRY_SPEED,
+                penalty: 0,
+            }),
+            // infos from: https://en.wikipedia.org/wiki/Naismith%27s_rule
+            elevation: match travel_type {
+                TravelType::Bicycle => Some(ElevationSettings {
+                    ascent: 400,
+                    descent: 0,
+                }),
+                TravelType::Pedestrian => Some(ElevationSettings {
+                    ascent: 600,
+                    descent: 100,
+                }),
+                TravelType::Wheelchair => Some(ElevationSettings {
+                    ascent: 1200,
+                    descent: 200,
+                }),
+                _ => None,
+            },
+        }
+    }
+
+    /// get speed forward, speed backward and penalty of a way, if it can be used with this profile
+    pub fn get_way_settings(&self, tags: &Tags) -> Option<(usize, usize, usize)> {
+        // ferries are handled like a highway class without maxspeed
+        let ferry_settings;
+        let is_ferry = osm_parsing::is_ferry(tags);
+        let settings = if is_ferry {
+            let ferries = self.ferries.as_ref()?;
+            ferry_settings = HighwaySettings {
+                allowed: true,
+                speed: ferries.speed,
+                penalty: ferries.penalty,
+                sidewalk_only: false,
+            };
+            Some(&ferry_settings)
+        } else {
+            let highway = tags.get("highway")?.trim();
+            self.highways.get(highway).or(self.default_highway.as_ref())
+        };
+        let mut allowed = settings
+            .is_some_and(|s| s.allowed && (!s.sidewalk_only || osm_parsing::has_sidewalk(tags)));
+
+        let max_speed = |key: &str| {
+            tags.get(key)
+                .and_then(|s| osm_parsing::parse_max_speed(s.trim()))
+        };
+        let mut speed = match settings {
+            Some(settings) if self.use_maxspeed && !is_ferry => {
+                let speed = match self.travel_type {
+                    TravelType::Hgv => max_speed("maxspeed:hgv").or_else(|| max_speed("maxspeed")),
+                    _ => max_speed("maxspeed"),
+                }
+                .unwrap_or(settings.speed);
+                Some((
+                    max_speed("maxspeed:forward").unwrap_or(speed),
+                    max_speed("maxspeed:backward").unwrap_or(speed),
+                ))
+            }
+            Some(settings) => Some((settings.speed, settings.speed)),
+            None => None,
+        };
+        let mut penalty = settings.map_or(0, |s| s.penalty);
+
+        // explicit permissions of the travel type allow forbidden highway classes
+        match osm_parsing::parse_access(tags, self.travel_type) {
+            Some((Access::No, _)) => allowed = false,
+            Some((Access::Destination, _)) => penalty += DESTINATION_PENALTY,
+            Some((Access::Yes, key)) if key != "access" => allowed = true,
+            _ => (),
+        }
+
+        for tag_override in self.overrides.iter().filter(|o| o.matches(tags)) {
+            if let Some(tag_allowed) = tag_override.allowed {
+                allowed = tag_allowed;
+            }
+            if let Some(tag_speed) = tag_override.speed {
+                speed = Some((tag_speed, tag_speed));
+            }
+            if let Some(tag_penalty) = tag_override.penalty {
+                penalty = tag_penalty;
+            }
+        }
+
+        // no permission lets a vehicle pass a way it does not fit on or which is too steep
+        if let Some(vehicle) = &self.vehicle {
+            allowed &= vehicle.fits(tags);
+        }
+        let incline = tags
+            .get("incline")
+            .and_then(|s| osm_parsing::parse_incline(s));
+        if let (Some(max_incline), Some(incline)) = (self.max_incline, incline) {
+            allowed &= incline <= max_incline as f32;
+        }
+
+        match speed {
+            Some((forward, backward)) if allowed => Some((forward, backward, penalty)),
+            _ => None,
+        }
+    }
+
+    /// get the speed factor and the penalty in percent depending on the surface of a way,
+    /// if it is not too rough for this profile
+    pub fn get_surface_settings(&self, tags: &Tags) -> Option<(usize, usize)> {
+        let settings = match &self.surfaces {
+            Some(settings) => settings,
+            None => return Some((100, 0)),
+        };
+        let mut speed_factor = match settings.speed_factors {
+            true => osm_parsing::parse_surface(tags),
+            false => 100,
+        };
+        if speed_factor < settings.min_factor {
+            return None;
+        }
+        if osm_parsing::has_cycleway(tags) {
+            speed_factor = speed_factor * settings.cycleway_factor / 100;
+        }
+        let penalty = match osm_parsing::is_unpaved(tags) {
+            true => settings.unpaved_penalty,
+            false => 0,
+        };
+        Some((speed_factor, penalty))
+    }
+
+    /// get the penalty in seconds for passing a node, if it can be passed with this profile
+    pub fn get_node_settings(&self, tags: &Tags) -> Option<usize> {
+        let mut allowed = true;
+        let mut penalty = 0;
+        let kerb = tags
+            .get("kerb")
+            .and_then(|kerb| self.barriers.get(&format!("kerb={}", kerb.trim())));
+        if let Some(barrier) = tags.get("barrier") {
+            // the kerb-value is more specific
+            let settings = self.barriers.get(barrier.trim()).filter(|_| kerb.is_none());
+            if let Some(settings) = settings {
+                allowed = settings.allowed;
+                penalty += settings.penalty;
+            }
+            // access-tags of the barrier, e.g. a gate with access=private
+            match osm_parsing::parse_access(tags, self.travel_type) {
+                Some((Access::No, _)) => allowed = false,
+                Some((Access::Yes, key)) if key != "access" => allowed = true,
+                _ => (),
+            }
+        }
+        // the access-tags do not allow to pass a kerb
+        if let Some(settings) = kerb {
+            allowed &= settings.allowed;
+            penalty += settings.penalty;
+        }
+        if let Some(highway) = tags.get("highway") {
+            if let Some(settings) = self.traffic_controls.get(highway.trim()) {
+                allowed &= settings.allowed;
+                penalty += settings.penalty;
+            }
+        }
+        // e.g. height restrictors
+        if let Some(vehicle) = &self.vehicle {
+            allowed &= vehicle.fits(tags);
+        }
+        allowed.then_some(penalty)
+    }
+}
+
+/// built-in profiles, which can be overwritten by profiles of the same name
+pub fn builtin_profiles() -> Vec<Profile> {
+    vec![
+        Profile::builtin(TravelType::Car),
+        Profile::builtin(TravelType::Hgv),
+        Profile::builtin(TravelType::Bicycle),
+        Profile::builtin(TravelType::Pedestrian),
+        Profile::builtin(TravelType::Wheelchair),
+        Profile::builtin(TravelType::All),
+    ]
+}
+
+/// read a list of profiles from a json-file
+pub fn read_profiles(filename: &str) -> Vec<Profile> {
+    let path = Path::new(&filename);
+    if !path.exists() {
+        println!("{} not found", filename);
+        std::process::exit(1);
+    }
+    let reader = BufReader::new(File::open(path).unwrap());
+    match serde_json::from_reader(reader) {
+        Ok(profiles) => profiles,
+        Err(e) => {
+            println!("could not parse {}: {}", filename, e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// find the profiles by name, preferring the ones of the profile-file
+pub fn select_profiles(names: &[String], profiles_file: Option<&str>) -> Vec<Profile> {
+    let mut available = builtin_profiles();
+    if let Some(filename) = profiles_file {
+        let custom = read_profiles(filename);
+        available.retain(|builtin| !custom.iter().any(|p| p.name == builtin.name));
+        available.extend(custom);
+    }
+
+    names
+        .iter()
+        .map(|name| match available.iter().find(|p| &p.name == name) {
+            Some(profile) => profile.clone(),
+            None => {
+                let known: Vec<&str> = available.iter().map(|p| p.name.as_str()).collect();
+                println!("unknown profile {}, available: {}", name, known.join(", "));
+                std::process::exit(1);
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(pairs: &[(&str, &str)]) -> Tags {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string().into(), v.to_string().into()))
+            .collect()
+    }
+
+    #[test]
+    fn builtin_car() {
+        let car = Profile::builtin(TravelType::Car);
+        assert_eq!(
+            car.get_way_settings(&tags(&[("highway", "motorway")])),
+            Some((120, 120, 0))
+        );
+        assert_eq!(
+            car.get_way_settings(&tags(&[("highway", "primary"), ("maxspeed", "70")])),
+            Some((70, 70, 0))
+        );
+        assert_eq!(car.get_way_settings(&tags(&[("highway", "footway")])), None);
+        assert_eq!(
+            car.get_way_settings(&tags(&[("highway", "construction")])),
+            None
+        );
+        assert_eq!(
+            car.get_way_settings(&tags(&[("highway", "something_new")])),
+            Some((50, 50, 0))
+        );
+        assert_eq!(car.get_way_settings(&tags(&[("railway", "rail")])), None);
+        assert_eq!(
+            car.get_way_settings(&tags(&[
+                ("highway", "primary"),
+                ("maxspeed", "FR:rural"),
+                ("maxspeed:backward", "30 mph")
+            ])),
+            Some((80, 48, 0))
+        );
+    }
+
+    #[test]
+    fn builtin_pedestrian_sidewalk() {
+        let pedestrian = Profile::builtin(TravelType::Pedestrian);
+        assert_eq!(
+            pedestrian.get_way_settings(&tags(&[("highway", "primary")])),
+            None
+        );
+        assert_eq!(
+            pedestrian.get_way_settings(&tags(&[("highway", "primary"), ("sidewalk", "both")])),
+            Some((PEDESTRIAN_SPEED, PEDESTRIAN_SPEED, 0))
+        );
+        assert_eq!(
+            pedestrian.get_way_settings(&tags(&[("highway", "primary"), ("sidewalk", "no")])),
+            None
+        );
+        assert_eq!(
+            pedestrian.get_way_settings(&tags(&[("highway", "footway"), ("maxspeed", "30")])),
+            Some((PEDESTRIAN_SPEED, PEDESTRIAN_SPEED, 0))
+        );
+    }
+
+    #[test]
+    fn access_tags() {
+        let car = Profile::builtin(TravelType::Car);
+        let bicycle = Profile::builtin(TravelType::Bicycle);
+        let pedestrian = Profile::builtin(TravelType::Pedestrian);
+
+        let private = tags(&[("highway", "service"), ("access", "private")]);
+        assert_eq!(car.get_way_settings(&private), None);
+        assert_eq!(pedestrian.get_way_settings(&private), None);
+
+        let footway = tags(&[("highway", "footway"), ("bicycle", "yes")]);
+        assert_eq!(bicycle.get_way_settings(&footway), Some((4, 4, 0)));
+        assert_eq!(car.get_way_settings(&footway), None);
+
+        // general permissions do not open up motorways
+        let motorway = tags(&[("highway", "motorway"), ("access", "yes")]);
+        assert_eq!(pedestrian.get_way_settings(&motorway), None);
+
+        let destination = tags(&[("highway", "residential"), ("vehicle", "destination")]);
+        assert_eq!(
+            car.get_way_settings(&destination),
+            Some((30, 30, DESTINATION_PENALTY))
+        );
+        assert_eq!(
+            pedestrian.get_way_settings(&destination),
+            Some((PEDESTRIAN_SPEED, PEDESTRIAN_SPEED, 0))
+        );
+    }
+
+    #[test]
+    fn overrides() {
+        let mut bicycle = Profile::builtin(TravelType::Bicycle);
+        bicycle.overrides = vec![
+            TagOverride {
+                key: "bicycle".to_string(),
+                value: Some("no".to_string()),
+                allowed: Some(false),
+                speed: None,
+                penalty: None,
+            },
+            TagOverride {
+                key: "bicycle".to_string(),
+                value: Some("yes".to_string()),
+                allowed: Some(true),
+                speed: Some(10),
+                penalty: Some(20),
+            },
+        ];
+        assert_eq!(
+            bicycle.get_way_settings(&tags(&[("highway", "path"), ("bicycle", "no")])),
+            None
+        );
+        assert_eq!(
+            bicycle.get_way_settings(&tags(&[("highway", "footway"), ("bicycle", "yes")])),
+            Some((10, 10, 20))
+        );
+        assert_eq!(
+            bicycle.get_way_settings(&tags(&[("highway", "footway")])),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_profile() {
+        let profiles: Vec<Profile> = serde_json::from_str(
+            r#"[{
+                "name": "scooter",
+                "travel_type": "bicycle",
+                "highways": {
+                    "residential": { "speed": 25 },
+                    "cycleway": { "speed": 25, "penalty": 10 },
+                    "primary": { "speed": 25, "sidewalk_only": true },
+                    "path": { "allowed": false }
+                },
+                "default_highway": { "speed": 15 },
+                "max_speed": 25
+            }]"#,
+        )
+        .unwrap();
+        assert_eq!(profiles.len(), 1);
+        let scooter = &profiles[0];
+        assert!(scooter.use_maxspeed);
+        assert_eq!(
+            scooter.get_way_settings(&tags(&[("highway", "cycleway")])),
+            Some((25, 25, 10))
+        );
+        assert_eq!(
+            scooter.get_way_settings(&tags(&[("highway", "residential"), ("maxspeed", "30")])),
+            Some((30, 30, 0))
+        );
+        assert_eq!(
+            scooter.get_way_settings(&tags(&[("highway", "tertiary")])),
+            Some((15, 15, 0))
+        );
+        assert_eq!(
+            scooter.get_way_settings(&tags(&[("highway", "path")])),
+            None
+        );
+        assert_eq!(
+            scooter.get_way_settings(&tags(&[("highway", "primary")])),
+            None
+        );
+    }
+
+    #[test]
+    fn climbing() {
+        let bicycle = Profile::builtin(TravelType::Bicycle);
+        let car = Profile::builtin(TravelType::Car);
+        // 1 km with 100 m of ascent
+        let edge = OsmWay {
+            source: 0,
+            target: 1,
+            speed: 20,
+            distance: DIST_MULTIPLICATOR,
+            penalty: 0,
+            way: 0,
+            ascent: 1000,
+            descent: 0,
+            ferry: false,
+            delay: 0,
+            speed_factor: 100,
+            duration: None,
+        };
+        let flat = OsmWay { ascent: 0, ..edge };
+        let weight = |edge, profile, optimize_by| Way::from((edge, profile, optimize_by)).weight;
+
+        // 400 seconds per 100 m of ascent
+        let climb = 400 * DIST_MULTIPLICATOR / 3600;
+        assert_eq!(
+            weight(edge, &bicycle, OptimizeBy::Time),
+            weight(flat, &bicycle, OptimizeBy::Time) + climb
+        );
+        assert_eq!(
+            weight(edge, &bicycle, OptimizeBy::Distance),
+            DIST_MULTIPLICATOR
+        );
+        assert_eq!(
+            weight(edge, &car, OptimizeBy::Time),
+            weight(flat, &car, OptimizeBy::Time)
+        );
+    }
+
+    #[test]
+    fn ferries() {
+        let car = Profile::builtin(TravelType::Car);
+        let pedestrian = Profile::builtin(TravelType::Pedestrian);
+        let ferry = tags(&[("route", "ferry"), ("maxspeed", "30")]);
+        assert_eq!(car.get_way_settings(&ferry), Some((5, 5, 0)));
+        assert_eq!(pedestrian.get_way_settings(&ferry), Some((5, 5, 0)));
+        assert_eq!(
+            car.get_way_settings(&tags(&[("route", "shuttle_train")])),
+            Some((5, 5, 0))
+        );
+        assert_eq!(
+            car.get_way_settings(&tags(&[("route", "ferry"), ("motor_vehicle", "no")])),
+            None
+        );
+        assert_eq!(
+            pedestrian.get_way_settings(&tags(&[("route", "ferry"), ("motor_vehicle", "no")])),
+            Some((5, 5, 0))
+        );
+
+        let mut without_ferries = car.clone();
+        without_ferries.ferries = None;
+        assert_eq!(without_ferries.get_way_settings(&ferry), None);
+    }
+
+    #[test]
+    fn node_settings() {
+        let car = Profile::builtin(TravelType::Car);
+        let bicycle = Profile::builtin(TravelType::Bicycle);
+        let bollard = tags(&[("barrier", "bollard")]);
+        assert_eq!(car.get_node_settings(&bollard), None);
+        assert_eq!(bicycle.get_node_settings(&bollard), Some(0));
+        assert_eq!(
+            car.get_node_settings(&tags(&[("barrier", "gate")])),
+            Some(30)
+        );
+        assert_eq!(
+            bicycle.get_node_settings(&tags(&[("barrier", "gate"), ("access", "private")])),
+            None
+        );
+        assert_eq!(
+            car.get_node_settings(&tags(&[("barrier", "bollard"), ("motor_vehicle", "yes")])),
+            Some(0)
+        );
+        assert_eq!(
+            car.get_node_settings(&tags(&[("highway", "traffic_signals")])),
+            Some(10)
+        );
+        assert_eq!(
+            car.get_node_settings(&tags(&[("barrier", "kerb"), ("highway", "bus_stop")])),
+            Some(0)
+        );
+    }
+
+    #[test]
+    fn surfaces() {
+        let car = Profile::builtin(TravelType::Car);
+        let bicycle = Profile::builtin(TravelType::Bicycle);
+        let pedestrian = Profile::builtin(TravelType::Pedestrian);
+        let track = tags(&[("highway", "track"), ("tracktype", "grade5")]);
+        assert_eq!(bicycle.get_surface_settings(&track), Some((40, 0)));
+        assert_eq!(car.get_surface_settings(&track), Some((100, 50)));
+        assert_eq!(pedestrian.get_surface_settings(&track), Some((100, 0)));
+
+        let cycleway = tags(&[("highway", "primary"), ("cycleway", "track")]);
+        assert_eq!(bicycle.get_surface_settings(&cycleway), Some((110, 0)));
+        assert_eq!(car.get_surface_settings(&cycleway), Some((100, 0)));
+        let sett = tags(&[("highway", "residential"), ("surface", "sett")]);
+        assert_eq!(car.get_surface_settings(&sett), Some((100, 0)));
+
+        // the cycleway factor does not exceed the maximum speed
+        let edge = OsmWay {
+            source: 0,
+            target: 1,
+            speed: BICYCLE_SPEED,
+            distance: DIST_MULTIPLICATOR,
+            penalty: 0,
+            way: 0,
+            ascent: 0,
+            descent: 0,
+            ferry: false,
+            delay: 0,
+            speed_factor: CYCLEWAY_FACTOR,
+            duration: None,
+        };
+        assert_eq!(
+            Way::from((edge, &bicycle, OptimizeBy::Time)).weight,
+            DIST_MULTIPLICATOR / BICYCLE_SPEED
+        );
+    }
+
+    #[test]
+    fn wheelchair() {
+        let wheelchair = Profile::builtin(TravelType::Wheelchair);
+        let settings = |pairs: &[(&str, &str)]| wheelchair.get_way_settings(&tags(pairs));
+        assert_eq!(settings(&[("highway", "footway")]), Some((4, 4, 0)));
+        assert_eq!(settings(&[("highway", "steps")]), None);
+        assert_eq!(settings(&[("highway", "steps"), ("foot", "yes")]), None);
+        assert_eq!(
+            settings(&[("highway", "steps"), ("ramp:wheelchair", "yes")]),
+            Some((4, 4, 0))
+        );
+        assert_eq!(
+            settings(&[("highway", "footway"), ("wheelchair", "no")]),
+            None
+        );
+        assert_eq!(
+            settings(&[("highway", "footway"), ("wheelchair", "limited")]),
+            Some((4, 4, 50))
+        );
+        assert_eq!(
+            settings(&[("highway", "footway"), ("incline", "12%")]),
+            None
+        );
+        assert_eq!(
+            settings(&[
+                ("highway", "footway"),
+                ("incline", "15%"),
+                ("foot", "designated")
+            ]),
+            None
+        );
+        assert_eq!(
+            settings(&[("highway", "footway"), ("incline", "-5%")]),
+            Some((4, 4, 0))
+        );
+        assert_eq!(settings(&[("highway", "primary")]), None);
+        assert_eq!(
+            settings(&[("highway", "primary"), ("sidewalk", "both")]),
+            Some((4, 4, 0))
+        );
+
+        let surface = |pairs: &[(&str, &str)]| wheelchair.get_surface_settings(&tags(pairs));
+        assert_eq!(surface(&[("surface", "asphalt")]), Some((100, 0)));
+        assert_eq!(surface(&[("surface", "gravel")]), Some((70, 50)));
+        assert_eq!(surface(&[("smoothness", "horrible")]), None);
+        assert_eq!(surface(&[("surface", "sand")]), None);
+
+        let node = |pairs: &[(&str, &str)]| wheelchair.get_node_settings(&tags(pairs));
+        assert_eq!(node(&[("barrier", "kerb"), ("kerb", "raised")]), None);
+        assert_eq!(
+            node(&[("barrier", "kerb"), ("kerb", "raised"), ("foot", "yes")]),
+            None
+        );
+        assert_eq!(node(&[("barrier", "kerb"), ("kerb", "lowered")]), Some(0));
+        assert_eq!(node(&[("barrier", "kerb")]), Some(30));
+        assert_eq!(node(&[("highway", "crossing"), ("kerb", "raised")]), None);
+        assert_eq!(node(&[("barrier", "stile")]), None);
+        assert_eq!(node(&[("highway", "elevator")]), Some(60));
+    }
+
+    #[test]
+    fn hgv() {
+        let hgv = Profile::builtin(TravelType::Hgv);
+        let settings = |pairs: &[(&str, &str)]| hgv.get_way_settings(&tags(pairs));
+        assert_eq!(settings(&[("highway", "motorway")]), Some((80, 80, 0)));
+        assert_eq!(
+            settings(&[
+                ("highway", "primary"),
+                ("maxspeed", "100"),
+                ("maxspeed:hgv", "60")
+            ]),
+            Some((60, 60, 0))
+        );
+        assert_eq!(
+            settings(&[("highway", "primary"), ("maxweight", "7.5")]),
+            None
+        );
+        assert_eq!(
+            settings(&[("highway", "primary"), ("maxheight", "3.5")]),
+            None
+        );
+        assert_eq!(
+            settings(&[
+                ("highway", "primary"),
+                ("maxheight", "3.5"),
+                ("hgv", "designated")
+            ]),
+            None
+        );
+        assert_eq!(
+            settings(&[
+                ("highway", "primary"),
+                ("maxheight", "3.5"),
+                ("motor_vehicle", "yes")
+            ]),
+            None
+        );
+        assert_eq!(
+            settings(&[("highway", "primary"), ("maxwidth", "2.3")]),
+            None
+        );
+        assert_eq!(
+            settings(&[("highway", "primary"), ("maxlength", "12")]),
+            None
+        );
+        assert_eq!(
+            settings(&[
+                ("highway", "primary"),
+                ("maxheight", "4.5"),
+                ("maxweight", "44 t")
+            ]),
+            Some((60, 60, 0))
+        );
+        assert_eq!(settings(&[("highway", "primary"), ("hgv", "no")]), None);
+        assert_eq!(
+            settings(&[("highway", "primary"), ("hgv", "destination")]),
+            Some((60, 60, 100))
+        );
+        // only unconditional restrictions are used
+        assert_eq!(
+            settings(&[
+                ("highway", "primary"),
+                ("hgv:conditional", "no @ (22:00-06:00)")
+            ]),
+            Some((60, 60, 0))
+        );
+        assert_eq!(settings(&[("highway", "footway")]), None);
+
+        let node = |pairs: &[(&str, &str)]| hgv.get_node_settings(&tags(pairs));
+        assert_eq!(
+            node(&[("barrier", "height_restrictor"), ("maxheight", "3.2")]),
+            None
+        );
+
+        // other dimensions can be configured
+        let mut van = hgv.clone();
+        van.vehicle = Some(VehicleSettings {
+            weight: Some(3.5),
+            height: Some(2.8),
+            width: None,
+            length: None,
+        });
+        assert_eq!(
+            van.get_way_settings(&tags(&[("highway", "primary"), ("maxheight", "3.5")])),
+            Some((60, 60, 0))
+        );
+    }
+}
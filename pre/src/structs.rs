@@ -1,44 +1,129 @@
-use crate::OPTIMIZE_BY;
-use crate::TRAVEL_TYPE;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::convert::From;
+use std::fmt;
+use std::str::FromStr;
 
 use crate::constants::*;
+use crate::id_mapping::OsmIdMapping;
+use crate::profile::Profile;
+use osmpbfreader::Tags;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum TravelType {
     Car,
+    /// heavy goods vehicle, using the same streets as cars
+    Hgv,
     CarBicycle,
     Bicycle,
     BicyclePedestrian,
     Pedestrian,
+    /// step-free pedestrian, as street type usable by pedestrians and wheelchairs
+    Wheelchair,
     All,
     Undefined,
 }
 
-#[derive(Debug, PartialEq, Serialize)]
+impl FromStr for TravelType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "car" => Ok(TravelType::Car),
+            "hgv" | "truck" => Ok(TravelType::Hgv),
+            "bicycle" | "bike" => Ok(TravelType::Bicycle),
+            "pedestrian" | "foot" => Ok(TravelType::Pedestrian),
+            "wheelchair" => Ok(TravelType::Wheelchair),
+            "all" => Ok(TravelType::All),
+            _ => Err(format!("unknown travel type: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for TravelType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            TravelType::Car => "car",
+            TravelType::Hgv => "hgv",
+            TravelType::CarBicycle => "car_bicycle",
+            TravelType::Bicycle => "bicycle",
+            TravelType::BicyclePedestrian => "bicycle_pedestrian",
+            TravelType::Pedestrian => "pedestrian",
+            TravelType::Wheelchair => "wheelchair",
+            TravelType::All => "all",
+            TravelType::Undefined => "undefined",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// result of the access-tags
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Access {
+    Yes,
+    /// only for reaching a destination on the way
+    Destination,
+    No,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
 pub enum OptimizeBy {
-    #[allow(dead_code)]
     Time,
-    #[allow(dead_code)]
     Distance,
 }
 
+impl FromStr for OptimizeBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "time" => Ok(OptimizeBy::Time),
+            "distance" => Ok(OptimizeBy::Distance),
+            _ => Err(format!("unknown metric: {}", s)),
+        }
+    }
+}
+
+impl fmt::Display for OptimizeBy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptimizeBy::Time => write!(f, "time"),
+            OptimizeBy::Distance => write!(f, "distance"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub struct OsmWay {
     pub source: NodeId,
     pub target: NodeId,
     pub speed: usize,
     pub distance: usize,
+    /// additional costs in percent
+    pub penalty: usize,
+    /// way the edge is part of
+    pub way: WayId,
+    /// climbs in decimeters
+    pub ascent: usize,
+    pub descent: usize,
+    /// ferries are not limited by the max speed of the profile
+    pub ferry: bool,
+    /// seconds for passing the target node, e.g. at traffic signals
+    pub delay: usize,
+    /// speed in percent of the usual speed, e.g. on rough surfaces
+    pub speed_factor: usize,
+    /// milliseconds for passing the edge, if the way has a tagged duration, e.g. ferries
+    pub duration: Option<usize>,
 }
 
-#[derive(Serialize, Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Eq, PartialEq)]
 pub struct Way {
     pub source: NodeId,
     pub target: NodeId,
     pub weight: usize,
-    #[serde(skip_serializing)]
+    #[serde(skip)]
     pub id: Option<EdgeId>,
     pub contrated_previous: Option<EdgeId>,
     pub contrated_next: Option<EdgeId>,
@@ -58,6 +143,7 @@ impl Ord for Way {
             .then(self.weight.cmp(&other.weight))
             .then(self.contrated_previous.cmp(&other.contrated_previous))
             .then(self.contrated_next.cmp(&other.contrated_next))
+            .then(self.id.cmp(&other.id))
     }
 }
 
@@ -105,38 +191,106 @@ impl Way {
     }
 }
 
-impl From<OsmWay> for Way {
-    fn from(full_edge: OsmWay) -> Self {
-        let mut speed: usize = match TRAVEL_TYPE {
-            TravelType::Car => full_edge.speed,
-            TravelType::CarBicycle => full_edge.speed,
-            TravelType::Bicycle if full_edge.speed <= 20 => full_edge.speed,
-            TravelType::Bicycle => 20,
-            TravelType::BicyclePedestrian if full_edge.speed <= 20 => full_edge.speed,
-            TravelType::BicyclePedestrian => 20,
-            TravelType::Pedestrian => 7,
-            TravelType::All => full_edge.speed,
-            TravelType::Undefined => 1,
-        };
+impl From<(OsmWay, &Profile, OptimizeBy)> for Way {
+    fn from((full_edge, profile, optimize_by): (OsmWay, &Profile, OptimizeBy)) -> Self {
+        let mut speed = full_edge.speed * full_edge.speed_factor / 100;
+        if let Some(max_speed) = profile.max_speed {
+            if speed > max_speed && !full_edge.ferry {
+                speed = max_speed;
+            }
+        }
         if speed == 0 {
             speed = 1;
         }
-        let weight = match OPTIMIZE_BY {
-            OptimizeBy::Distance => full_edge.distance,
-            OptimizeBy::Time => full_edge.distance / speed,
+        let mut weight = match (optimize_by, full_edge.duration) {
+            (OptimizeBy::Distance, _) => full_edge.distance,
+            (OptimizeBy::Time, Some(duration)) => duration * DIST_MULTIPLICATOR / 3_600_000,
+            (OptimizeBy::Time, None) => full_edge.distance / speed,
         };
+        if optimize_by == OptimizeBy::Time {
+            let mut seconds = full_edge.delay as f32;
+            // climbing takes additional time
+            if let Some(elevation) = &profile.elevation {
+                seconds += (full_edge.ascent * elevation.ascent
+                    + full_edge.descent * elevation.descent) as f32
+                    / 1000.0;
+            }
+            weight += (seconds * DIST_MULTIPLICATOR as f32 / 3600.0) as usize;
+        }
+        let weight = weight * (100 + full_edge.penalty) / 100;
         Way::new(full_edge.source, full_edge.target, weight)
     }
 }
 
-#[derive(Serialize, Debug, Clone)]
+/// restriction relation with osm-ids
+#[derive(Debug, Clone)]
+pub struct OsmRestriction {
+    pub from: i64,
+    pub via_node: Option<i64>,
+    pub via_ways: Vec<i64>,
+    pub to: i64,
+    pub tags: Tags,
+}
+
+/// information about an osm-way, the strings are interned
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct WayInfo {
+    pub osm_id: i64,
+    pub name: usize,
+    pub reference: usize,
+    pub highway: usize,
+    /// ferry or car-shuttle route
+    pub ferry: bool,
+    /// travel time in seconds of the whole way, if tagged
+    #[serde(skip)]
+    pub duration: Option<usize>,
+}
+
+/// interned strings, the empty string has the id 0
+#[derive(Debug, Clone)]
+pub struct StringTable {
+    pub strings: Vec<String>,
+    ids: HashMap<String, usize>,
+}
+
+impl StringTable {
+    pub fn new() -> Self {
+        let mut table = StringTable {
+            strings: Vec::new(),
+            ids: HashMap::new(),
+        };
+        table.get_id("");
+        table
+    }
+
+    pub fn get_id(&mut self, s: &str) -> usize {
+        if let Some(id) = self.ids.get(s) {
+            return *id;
+        }
+        self.strings.push(s.to_string());
+        self.ids.insert(s.to_string(), self.strings.len() - 1);
+        self.strings.len() - 1
+    }
+}
+
+/// restricted sequence of nodes: from-node, via-nodes, to-node
+#[derive(Debug, Clone, PartialEq)]
+pub struct TurnRestriction {
+    pub nodes: Vec<NodeId>,
+    /// way of every pair of consecutive nodes, to tell parallel edges apart
+    pub ways: Vec<WayId>,
+    /// only this turn is allowed, instead of being forbidden
+    pub only: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Node {
     pub latitude: f32,
     pub longitude: f32,
     pub rank: Rank,
 }
 
-#[derive(Serialize)]
+#[derive(Deserialize, Serialize)]
 pub struct GridBounds {
     pub lat_min: f32,
     pub lat_max: f32,
@@ -144,7 +298,36 @@ pub struct GridBounds {
     pub lng_max: f32,
 }
 
-#[derive(Serialize)]
+/// intermediate coordinates (latitude, longitude) of the edges.
+/// the ones of edge i are coordinates[offset[i]..offset[i + 1]]
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq)]
+pub struct Geometry {
+    pub offset: Vec<usize>,
+    pub coordinates: Vec<(f32, f32)>,
+    /// elevation in meters of every coordinate (empty without elevation data)
+    pub elevations: Vec<Option<f32>>,
+}
+
+impl Geometry {
+    /// add the coordinates of the next edge
+    pub fn push(&mut self, coordinates: &[(f32, f32)]) {
+        if self.offset.is_empty() {
+            self.offset.push(0);
+        }
+        self.coordinates.extend_from_slice(coordinates);
+        self.offset.push(self.coordinates.len());
+    }
+
+    /// coordinates of an edge (empty for unknown edges)
+    pub fn get(&self, edge: EdgeId) -> &[(f32, f32)] {
+        if edge + 1 >= self.offset.len() {
+            return &[];
+        }
+        &self.coordinates[self.offset[edge]..self.offset[edge + 1]]
+    }
+}
+
+#[derive(Deserialize, Serialize)]
 pub struct FmiFile {
     pub nodes: Vec<Node>,
     pub up_offset: Vec<EdgeId>,
@@ -155,4 +338,56 @@ pub struct FmiFile {
     pub grid: Vec<NodeId>,
     pub grid_bounds: GridBounds,
     pub optimized_by: OptimizeBy,
+    /// only for edge-based graphs: original node reached by each node
+    pub original_nodes: Vec<NodeId>,
+    /// shape of the original edges (empty for shortcuts)
+    pub geometry: Geometry,
+    /// elevation in meters of every node (empty without elevation data)
+    pub elevations: Vec<Option<f32>>,
+    /// way of every edge (INVALID_WAY for shortcuts)
+    pub edge_ways: Vec<WayId>,
+    pub ways: Vec<WayInfo>,
+    /// interned names, refs and highway classes of the ways
+    pub strings: Vec<String>,
+    /// node of the profile graph reached by each node, only kept for the text export
+    #[serde(skip)]
+    pub input_nodes: Vec<NodeId>,
+}
+
+/// weighted graph of a profile and metric before the contraction, cached between runs
+#[derive(Deserialize, Serialize)]
+pub struct WeightedGraph {
+    /// identifies the input and the settings, has to stay the first field
+    pub key: String,
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Way>,
+    /// original edge of every edge, as the ids of the edges are not serialized
+    pub edge_ids: Vec<EdgeId>,
+    /// only for edge-based graphs: original node reached by each node
+    pub original_nodes: Vec<NodeId>,
+    pub optimized_by: OptimizeBy,
+    /// shape and way of every original edge
+    pub geometry: Geometry,
+    pub edge_ways: Vec<WayId>,
+    pub ways: Vec<WayInfo>,
+    pub strings: Vec<String>,
+}
+
+/// everything read from the osm-input
+pub struct OsmData {
+    pub nodes: Vec<Node>,
+    /// tags of barriers and traffic controls
+    pub node_tags: HashMap<NodeId, Tags>,
+    /// one list of osm-edges per profile
+    pub full_edges: Vec<Vec<OsmWay>>,
+    /// restriction relations and the nodes of their ways
+    pub restrictions: Vec<OsmRestriction>,
+    pub way_nodes: HashMap<i64, Vec<NodeId>>,
+    /// information about the used ways
+    pub ways: Vec<WayInfo>,
+    pub strings: StringTable,
+    /// mapping of own-ids and osm-ids
+    pub osm_id_mapping: OsmIdMapping,
+    /// length of the ways with a duration before clipping
+    pub way_lengths: HashMap<WayId, usize>,
 }
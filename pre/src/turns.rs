@@ -0,0 +1,688 @@
+use super::*;
+use crate::profile::TurnSettings;
+
+/// nodes next to the given node inside of a way
+fn get_way_neighbors(way: &[NodeId], node: NodeId) -> Vec<NodeId> {
+    let mut neighbors = Vec::<NodeId>::new();
+    for (i, _) in way.iter().enumerate().filter(|(_, n)| **n == node) {
+        if i > 0 {
+            neighbors.push(way[i - 1]);
+        }
+        if i + 1 < way.len() {
+            neighbors.push(way[i + 1]);
+        }
+    }
+    neighbors
+}
+
+/// continue the chain with the given way, if they are connected
+fn append_way(chain: &mut Vec<NodeId>, way: &[NodeId]) -> bool {
+    let last = *chain.last().unwrap();
+    if way.first() == Some(&last) {
+        chain.extend(way.iter().skip(1));
+        true
+    } else if way.last() == Some(&last) {
+        chain.extend(way.iter().rev().skip(1));
+        true
+    } else {
+        false
+    }
+}
+
+/// get the via-nodes of a restriction, starting at the from-way,
+/// and the osm-id of the via-way between every two of them
+fn get_via_nodes(
+    restriction: &OsmRestriction,
+    from_way: &[NodeId],
+    way_nodes: &HashMap<i64, Vec<NodeId>>,
+    osm_id_mapping: &OsmIdMapping,
+) -> Option<(Vec<NodeId>, Vec<i64>)> {
+    if let Some(via_node) = restriction.via_node {
+        return osm_id_mapping
+            .get(via_node)
+            .map(|id| (vec![id], Vec::new()));
+    }
+    let first_id = *restriction.via_ways.first()?;
+    let first_way = way_nodes.get(&first_id)?;
+    let from_ends = [*from_way.first()?, *from_way.last()?];
+    let mut chain = if from_ends.contains(first_way.first()?) {
+        first_way.clone()
+    } else if from_ends.contains(first_way.last()?) {
+        first_way.iter().rev().cloned().collect()
+    } else {
+        return None;
+    };
+    let mut chain_ways = vec![first_id; chain.len() - 1];
+    for way_id in restriction.via_ways.iter().skip(1) {
+        if !append_way(&mut chain, way_nodes.get(way_id)?) {
+            return None;
+        }
+        chain_ways.resize(chain.len() - 1, *way_id);
+    }
+    Some((chain, chain_ways))
+}
+
+/// resolve restriction relations into node sequences of the profile graph
+pub fn resolve_restrictions(
+    restrictions: &[OsmRestriction],
+    way_nodes: &HashMap<i64, Vec<NodeId>>,
+    osm_id_mapping: &OsmIdMapping,
+    node_mapping: &[NodeId],
+    ways: &[WayInfo],
+    travel_type: TravelType,
+) -> Vec<TurnRestriction> {
+    let way_ids: HashMap<i64, WayId> = (ways.iter().enumerate())
+        .map(|(i, way)| (way.osm_id, i))
+        .collect();
+    let mut result = Vec::<TurnRestriction>::new();
+    for restriction in restrictions {
+        let only = match osm_parsing::parse_restriction(&restriction.tags, travel_type) {
+            Some(only) => only,
+            None => continue,
+        };
+        let (from_way, to_way) = match (
+            way_nodes.get(&restriction.from),
+            way_nodes.get(&restriction.to),
+        ) {
+            (Some(from_way), Some(to_way)) if !from_way.is_empty() && !to_way.is_empty() => {
+                (from_way, to_way)
+            }
+            _ => continue,
+        };
+        let (via, via_ways) = match get_via_nodes(restriction, from_way, way_nodes, osm_id_mapping)
+        {
+            Some(via) => via,
+            None => continue,
+        };
+        let segment_ways: Option<Vec<WayId>> = std::iter::once(&restriction.from)
+            .chain(via_ways.iter())
+            .chain(Some(&restriction.to))
+            .map(|osm_id| way_ids.get(osm_id).copied())
+            .collect();
+        let segment_ways = match segment_ways {
+            Some(segment_ways) => segment_ways,
+            None => continue,
+        };
+
+        for from in get_way_neighbors(from_way, via[0]) {
+            for to in get_way_neighbors(to_way, *via.last().unwrap()) {
+                let nodes: Vec<NodeId> = std::iter::once(from)
+                    .chain(via.iter().cloned())
+                    .chain(Some(to))
+                    .map(|node| node_mapping[node])
+                    .collect();
+                // skip restrictions on ways, which are not part of the graph
+                if nodes.contains(&INVALID_NODE) {
+                    continue;
+                }
+                result.push(TurnRestriction {
+                    nodes,
+                    ways: segment_ways.clone(),
+                    only,
+                });
+            }
+        }
+    }
+    result
+}
+
+/// turning angle in degrees between 0 (straight) and 180 (u-turn)
+fn get_turn_angle(from: &Node, via: &Node, to: &Node) -> f32 {
+    let bearing = |a: &Node, b: &Node| {
+        let d_lng = (b.longitude - a.longitude) * a.latitude.to_radians().cos();
+        let d_lat = b.latitude - a.latitude;
+        d_lat.atan2(d_lng).to_degrees()
+    };
+    let angle = (bearing(via, to) - bearing(from, via)).abs() % 360.0;
+    if angle > 180.0 {
+        360.0 - angle
+    } else {
+        angle
+    }
+}
+
+/// copy of an edge, which is used for restrictions with via-ways.
+/// restrictions sharing the beginning of their via-ways share the copies
+#[derive(Default)]
+struct ViaCopy {
+    original: EdgeId,
+    /// only allowed next edges, the next via-edges and to-edges of only-restrictions
+    only: Vec<EdgeId>,
+    /// to-edges of the restrictions ending here
+    forbidden: Vec<EdgeId>,
+}
+
+/// build edge-based graph: every edge becomes a node and every allowed turn an edge.
+/// returns the new nodes, the turn-edges and the original edge of each node
+pub fn build_edge_based_graph(
+    full_edges: &[OsmWay],
+    ways: &[Way],
+    nodes: &[Node],
+    geometry: &Geometry,
+    restrictions: &[TurnRestriction],
+    settings: &TurnSettings,
+    optimize_by: OptimizeBy,
+) -> (Vec<Node>, Vec<Way>, Vec<NodeId>) {
+    let amount_edges = full_edges.len();
+
+    // outgoing edges of every node
+    let mut outgoing = vec![Vec::<EdgeId>::new(); nodes.len()];
+    // parallel edges between the same nodes are told apart by their way
+    let mut edge_lookup = HashMap::<(NodeId, NodeId, WayId), EdgeId>::new();
+    for (i, edge) in full_edges.iter().enumerate() {
+        outgoing[edge.source].push(i);
+        edge_lookup
+            .entry((edge.source, edge.target, edge.way))
+            .or_insert(i);
+    }
+    let lookup = |from: NodeId, to: NodeId, way: WayId| edge_lookup.get(&(from, to, way)).copied();
+
+    // restrictions with via-node: from-edge -> (only allowed next edges, forbidden next edges)
+    let mut node_restrictions = HashMap::<EdgeId, (Vec<EdgeId>, Vec<EdgeId>)>::new();
+    // restrictions with via-ways need copies of the via-edges.
+    // the turns of an edge or copy into the next via-edge lead to its copy
+    let mut copies = Vec::<ViaCopy>::new();
+    let mut redirects = HashMap::<(NodeId, EdgeId), NodeId>::new();
+    for restriction in restrictions {
+        let path: Option<Vec<EdgeId>> = (restriction.nodes.windows(2))
+            .zip(&restriction.ways)
+            .map(|(pair, way)| lookup(pair[0], pair[1], *way))
+            .collect();
+        let path = match path {
+            Some(path) => path,
+            None => continue,
+        };
+        if path.len() == 2 {
+            let entry = node_restrictions.entry(path[0]).or_default();
+            if restriction.only {
+                entry.0.push(path[1]);
+            } else {
+                entry.1.push(path[1]);
+            }
+            continue;
+        }
+
+        let via = &path[1..path.len() - 1];
+        let to = *path.last().unwrap();
+        if restriction.only {
+            let entry = node_restrictions.entry(path[0]).or_default();
+            entry.0.push(via[0]);
+        }
+        let mut previous = path[0];
+        for via_edge in via {
+            let copy = *redirects.entry((previous, *via_edge)).or_insert_with(|| {
+                copies.push(ViaCopy {
+                    original: *via_edge,
+                    ..Default::default()
+                });
+                amount_edges + copies.len() - 1
+            });
+            if restriction.only && previous >= amount_edges {
+                copies[previous - amount_edges].only.push(*via_edge);
+            }
+            previous = copy;
+        }
+        let last = &mut copies[previous - amount_edges];
+        if restriction.only {
+            last.only.push(to);
+        } else {
+            last.forbidden.push(to);
+        }
+    }
+
+    let get_turns = |edge: EdgeId| -> Vec<EdgeId> {
+        let from = &full_edges[edge];
+        let dead_end = outgoing[from.target]
+            .iter()
+            .all(|next| full_edges[*next].target == from.source);
+        outgoing[from.target]
+            .iter()
+            .filter(|next| {
+                let to = full_edges[**next].target;
+                if to == from.source && !settings.allow_u_turns && !dead_end {
+                    return false;
+                }
+                match node_restrictions.get(&edge) {
+                    Some((only, forbidden)) => {
+                        (only.is_empty() || only.contains(*next)) && !forbidden.contains(*next)
+                    }
+                    None => true,
+                }
+            })
+            .copied()
+            .collect()
+    };
+    let get_weight = |from: EdgeId, to: EdgeId| -> Weight {
+        let mut weight = ways[to].weight;
+        if optimize_by == OptimizeBy::Time && settings.max_turn_penalty > 0 {
+            // the shape next to the via-node gives the direction of the edges
+            let point = |(latitude, longitude): &(f32, f32)| Node {
+                latitude: *latitude,
+                longitude: *longitude,
+                rank: INVALID_RANK,
+            };
+            let before = geometry.get(from).last().map(point);
+            let after = geometry.get(to).first().map(point);
+            let angle = get_turn_angle(
+                before.as_ref().unwrap_or(&nodes[full_edges[from].source]),
+                &nodes[full_edges[from].target],
+                after.as_ref().unwrap_or(&nodes[full_edges[to].target]),
+            );
+            let seconds = settings.max_turn_penalty as f32 * angle / 180.0;
+            weight += (seconds * DIST_MULTIPLICATOR as f32 / 3600.0) as Weight;
+        }
+        weight
+    };
+
+    let mut turn_edges: Vec<Way> = (0..amount_edges)
+        .into_par_iter()
+        .map(|edge| {
+            get_turns(edge)
+                .into_iter()
+                .map(|next| {
+                    let target = *redirects.get(&(edge, next)).unwrap_or(&next);
+                    Way::new(edge, target, get_weight(edge, next))
+                })
+                .collect::<Vec<Way>>()
+        })
+        .flatten()
+        .collect();
+
+    for (i, copy) in copies.iter().enumerate() {
+        let edge = amount_edges + i;
+        for next in get_turns(copy.original) {
+            if (!copy.only.is_empty() && !copy.only.contains(&next))
+                || copy.forbidden.contains(&next)
+            {
+                continue;
+            }
+            let target = *redirects.get(&(edge, next)).unwrap_or(&next);
+            turn_edges.push(Way::new(edge, target, get_weight(copy.original, next)));
+        }
+    }
+
+    let original_edges: Vec<EdgeId> = (0..amount_edges)
+        .chain(copies.iter().map(|copy| copy.original))
+        .collect();
+    let edge_nodes: Vec<Node> = original_edges
+        .iter()
+        .map(|edge| Node {
+            latitude: nodes[full_edges[*edge].target].latitude,
+            longitude: nodes[full_edges[*edge].target].longitude,
+            rank: INVALID_RANK,
+        })
+        .collect();
+
+    (edge_nodes, turn_edges, original_edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use osmpbfreader::Tags;
+
+    fn node(latitude: f32, longitude: f32) -> Node {
+        Node {
+            latitude,
+            longitude,
+            rank: INVALID_RANK,
+        }
+    }
+
+    /// both directions of the given node pairs
+    fn two_way_edges(pairs: &[(NodeId, NodeId)]) -> Vec<OsmWay> {
+        pairs
+            .iter()
+            .flat_map(|(a, b)| vec![(*a, *b), (*b, *a)])
+            .map(|(source, target)| OsmWay {
+                source,
+                target,
+                speed: 50,
+                distance: 10,
+                penalty: 0,
+                way: 0,
+                ascent: 0,
+                descent: 0,
+                ferry: false,
+                delay: 0,
+                speed_factor: 100,
+                duration: None,
+            })
+            .collect()
+    }
+
+    /// all turns as node sequences
+    fn get_turns(edges: &[Way], full_edges: &[OsmWay]) -> Vec<Vec<NodeId>> {
+        let mut result: Vec<Vec<NodeId>> = edges
+            .iter()
+            .map(|way| {
+                let from = full_edges[way.source];
+                vec![from.source, from.target, full_edges[way.target].target]
+            })
+            .collect();
+        result.sort();
+        result
+    }
+
+    #[test]
+    fn turn_angle() {
+        let a = node(0.0, 0.0);
+        let b = node(0.0, 1.0);
+        assert!(get_turn_angle(&a, &b, &node(0.0, 2.0)) < 0.1);
+        assert!((get_turn_angle(&a, &b, &node(1.0, 1.0)) - 90.0).abs() < 0.1);
+        assert!((get_turn_angle(&a, &b, &node(-1.0, 1.0)) - 90.0).abs() < 0.1);
+        assert!((get_turn_angle(&a, &b, &a) - 180.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn resolve_via_node_and_via_way() {
+        // 0 - 1 - 2
+        //     |
+        //     3 - 4
+        let mut way_nodes = HashMap::<i64, Vec<NodeId>>::new();
+        way_nodes.insert(10, vec![0, 1, 2]);
+        way_nodes.insert(11, vec![3, 1]);
+        way_nodes.insert(12, vec![4, 3]);
+        let mut osm_id_mapping = OsmIdMapping::Hash(HashMap::new());
+        for osm_id in [99, 100] {
+            osm_id_mapping.insert(osm_id);
+        }
+        osm_id_mapping.finish();
+        let node_mapping: Vec<NodeId> = (0..5).collect();
+        let ways: Vec<WayInfo> = [10, 11, 12]
+            .iter()
+            .map(|osm_id| WayInfo {
+                osm_id: *osm_id,
+                name: 0,
+                reference: 0,
+                highway: 0,
+                ferry: false,
+                duration: None,
+            })
+            .collect();
+
+        let mut tags = Tags::new();
+        tags.insert("type".into(), "restriction".into());
+        tags.insert("restriction".into(), "no_left_turn".into());
+        let restrictions = vec![
+            OsmRestriction {
+                from: 11,
+                via_node: Some(100),
+                via_ways: vec![],
+                to: 10,
+                tags: tags.clone(),
+            },
+            OsmRestriction {
+                from: 12,
+                via_node: None,
+                via_ways: vec![11],
+                to: 10,
+                tags,
+            },
+        ];
+        let resolved = resolve_restrictions(
+            &restrictions,
+            &way_nodes,
+            &osm_id_mapping,
+            &node_mapping,
+            &ways,
+            TravelType::Car,
+        );
+        let expected = vec![
+            TurnRestriction {
+                nodes: vec![3, 1, 0],
+                ways: vec![1, 0],
+                only: false,
+            },
+            TurnRestriction {
+                nodes: vec![3, 1, 2],
+                ways: vec![1, 0],
+                only: false,
+            },
+            TurnRestriction {
+                nodes: vec![4, 3, 1, 0],
+                ways: vec![2, 1, 0],
+                only: false,
+            },
+            TurnRestriction {
+                nodes: vec![4, 3, 1, 2],
+                ways: vec![2, 1, 0],
+                only: false,
+            },
+        ];
+        assert_eq!(resolved, expected);
+
+        // pedestrians do not care
+        let resolved = resolve_restrictions(
+            &restrictions,
+            &way_nodes,
+            &osm_id_mapping,
+            &node_mapping,
+            &ways,
+            TravelType::Pedestrian,
+        );
+        assert!(resolved.is_empty());
+    }
+
+    #[test]
+    fn edge_based_restrictions() {
+        //     3
+        //     |
+        // 0 - 1 - 2
+        let nodes = vec![
+            node(0.0, 0.0),
+            node(0.0, 1.0),
+            node(0.0, 2.0),
+            node(1.0, 1.0),
+        ];
+        let full_edges = two_way_edges(&[(0, 1), (1, 2), (1, 3)]);
+        let ways: Vec<Way> = full_edges
+            .iter()
+            .map(|e| Way::new(e.source, e.target, e.distance))
+            .collect();
+        let restrictions = vec![
+            TurnRestriction {
+                nodes: vec![0, 1, 3],
+                ways: vec![0; 2],
+                only: false,
+            },
+            TurnRestriction {
+                nodes: vec![3, 1, 2],
+                ways: vec![0; 2],
+                only: true,
+            },
+        ];
+        let graph = build_edge_based_graph(
+            &full_edges,
+            &ways,
+            &nodes,
+            &Geometry::default(),
+            &restrictions,
+            &TurnSettings::default(),
+            OptimizeBy::Time,
+        );
+        assert_eq!(graph.0.len(), full_edges.len());
+        let original_nodes: Vec<NodeId> = graph.2.iter().map(|e| full_edges[*e].target).collect();
+        assert_eq!(original_nodes, vec![1, 0, 2, 1, 3, 1]);
+        assert_eq!(graph.0[0].longitude, 1.0);
+
+        // no u-turns except at dead ends, no 0->1->3, only 3->1->2
+        let expected = vec![
+            vec![0, 1, 2],
+            vec![1, 0, 1],
+            vec![1, 2, 1],
+            vec![1, 3, 1],
+            vec![2, 1, 0],
+            vec![2, 1, 3],
+            vec![3, 1, 2],
+        ];
+        assert_eq!(get_turns(&graph.1, &full_edges), expected);
+    }
+
+    #[test]
+    fn edge_based_via_way() {
+        // 0 - 1 - 2
+        //     |
+        //     3 - 4
+        let nodes = vec![
+            node(1.0, 0.0),
+            node(1.0, 1.0),
+            node(1.0, 2.0),
+            node(0.0, 1.0),
+            node(0.0, 2.0),
+        ];
+        let full_edges = two_way_edges(&[(0, 1), (1, 2), (1, 3), (3, 4)]);
+        let ways: Vec<Way> = full_edges
+            .iter()
+            .map(|e| Way::new(e.source, e.target, e.distance))
+            .collect();
+        let restrictions = vec![TurnRestriction {
+            nodes: vec![4, 3, 1, 0],
+            ways: vec![0; 3],
+            only: false,
+        }];
+        let graph = build_edge_based_graph(
+            &full_edges,
+            &ways,
+            &nodes,
+            &Geometry::default(),
+            &restrictions,
+            &TurnSettings {
+                restrictions: true,
+                max_turn_penalty: 10,
+                allow_u_turns: false,
+            },
+            OptimizeBy::Time,
+        );
+        // one copy of the via-edge 3->1
+        assert_eq!(graph.0.len(), full_edges.len() + 1);
+        assert_eq!(graph.2[full_edges.len()], 5);
+
+        let from = 7; // 4->3
+        let via = 5; // 3->1
+        let copy = full_edges.len();
+        let outgoing: Vec<&Way> = graph.1.iter().filter(|w| w.source == from).collect();
+        assert_eq!(outgoing.len(), 1);
+        assert_eq!(outgoing[0].target, copy);
+
+        let mut copy_targets: Vec<NodeId> = graph
+            .1
+            .iter()
+            .filter(|w| w.source == copy)
+            .map(|w| full_edges[w.target].target)
+            .collect();
+        copy_targets.sort();
+        assert_eq!(copy_targets, vec![2]);
+
+        let mut via_targets: Vec<NodeId> = graph
+            .1
+            .iter()
+            .filter(|w| w.source == via)
+            .map(|w| full_edges[w.target].target)
+            .collect();
+        via_targets.sort();
+        assert_eq!(via_targets, vec![0, 2]);
+
+        // turn penalties: straight is cheaper than turning
+        let weight = |from: EdgeId, to_node: NodeId| {
+            graph
+                .1
+                .iter()
+                .filter(|w| w.source == from && w.target < full_edges.len())
+                .find(|w| full_edges[w.target].target == to_node)
+                .unwrap()
+                .weight
+        };
+        assert!(weight(0, 2) < weight(0, 3));
+    }
+
+    #[test]
+    fn edge_based_shared_via_way() {
+        // 0 - 1 - 2
+        //    / \
+        //   5   3 - 4
+        let nodes = vec![
+            node(1.0, 0.0),
+            node(1.0, 1.0),
+            node(1.0, 2.0),
+            node(0.0, 1.5),
+            node(0.0, 2.5),
+            node(0.0, 0.5),
+        ];
+        let full_edges = two_way_edges(&[(0, 1), (1, 2), (1, 3), (3, 4), (1, 5)]);
+        let ways: Vec<Way> = full_edges
+            .iter()
+            .map(|e| Way::new(e.source, e.target, e.distance))
+            .collect();
+        // same from- and via-way, but different to-ways
+        let restrictions = vec![
+            TurnRestriction {
+                nodes: vec![4, 3, 1, 0],
+                ways: vec![0; 3],
+                only: false,
+            },
+            TurnRestriction {
+                nodes: vec![4, 3, 1, 2],
+                ways: vec![0; 3],
+                only: false,
+            },
+        ];
+        let graph = build_edge_based_graph(
+            &full_edges,
+            &ways,
+            &nodes,
+            &Geometry::default(),
+            &restrictions,
+            &TurnSettings::default(),
+            OptimizeBy::Time,
+        );
+        // one shared copy of the via-edge 3->1
+        assert_eq!(graph.0.len(), full_edges.len() + 1);
+        let copy = full_edges.len();
+        let targets = |from: NodeId| {
+            let mut targets: Vec<NodeId> = (graph.1.iter())
+                .filter(|w| w.source == from)
+                .map(|w| full_edges[graph.2[w.target]].target)
+                .collect();
+            targets.sort();
+            targets
+        };
+        assert_eq!(targets(copy), vec![5]);
+        // the via-edge itself is not restricted
+        assert_eq!(targets(5), vec![0, 2, 5]);
+    }
+
+    #[test]
+    fn edge_based_parallel_edges() {
+        // 0 - 1 = 2, the way 1 and the service road 2 both connect 1 and 2
+        let nodes = vec![node(0.0, 0.0), node(0.0, 1.0), node(0.0, 2.0)];
+        let mut full_edges = two_way_edges(&[(0, 1), (1, 2), (1, 2)]);
+        for (i, edge) in full_edges.iter_mut().enumerate() {
+            edge.way = i / 2;
+        }
+        let ways: Vec<Way> = full_edges
+            .iter()
+            .map(|e| Way::new(e.source, e.target, e.distance))
+            .collect();
+        // no turn from the way 0 into the service road
+        let restrictions = vec![TurnRestriction {
+            nodes: vec![0, 1, 2],
+            ways: vec![0, 2],
+            only: false,
+        }];
+        let graph = build_edge_based_graph(
+            &full_edges,
+            &ways,
+            &nodes,
+            &Geometry::default(),
+            &restrictions,
+            &TurnSettings::default(),
+            OptimizeBy::Time,
+        );
+        let targets: Vec<EdgeId> = (graph.1.iter())
+            .filter(|w| w.source == 0)
+            .map(|w| w.target)
+            .collect();
+        assert_eq!(targets, vec![2]);
+    }
+}
@@ -26,34 +26,38 @@ impl Dijkstra {
         }
     }
 
-    /// find path from start to end
+    /// find path from any of the starts to any of the ends as list of original edges.
+    /// for edge-based graphs every node of the path stands for the original node it reaches
     #[allow(clippy::too_many_arguments)]
     pub fn find_path(
         &mut self,
-        start: NodeId,
-        end: NodeId,
+        starts: &[NodeId],
+        ends: &[NodeId],
         nodes: &[Node],
         edges: &[Way],
         up_offset: &[EdgeId],
         down_offset: &[EdgeId],
         down_index: &[EdgeId],
-    ) -> Option<(Vec<NodeId>, f32)> {
+    ) -> Option<(Vec<EdgeId>, f32)> {
         self.heap_up.clear();
         self.heap_down.clear();
         self.visited_up.unvisit_all();
         self.visited_down.unvisit_all();
 
-        if start == end {
+        if starts.iter().any(|start| ends.contains(start)) {
             return Some((vec![], 0.0));
         }
 
-        self.dist_up[start] = (0, None);
-        self.dist_down[end] = (0, None);
-        self.visited_up.set_visited(start);
-        self.visited_down.set_visited(end);
-
-        self.heap_up.push(MinHeapItem::new(start, 0));
-        self.heap_down.push(MinHeapItem::new(end, 0));
+        for start in starts {
+            self.dist_up[*start] = (0, None);
+            self.visited_up.set_visited(*start);
+            self.heap_up.push(MinHeapItem::new(*start, 0));
+        }
+        for end in ends {
+            self.dist_down[*end] = (0, None);
+            self.visited_down.set_visited(*end);
+            self.heap_down.push(MinHeapItem::new(*end, 0));
+        }
 
         let mut best_weight = WEIGHT_MAX;
         let mut meeting_node = INVALID_NODE;
@@ -156,16 +160,15 @@ impl Dijkstra {
         weight: Weight,
         meeting_rank: Rank,
         edges: &[Way],
-    ) -> (Vec<NodeId>, f32) {
+    ) -> (Vec<EdgeId>, f32) {
         assert!(self.visited_up.is_visited(meeting_node));
         assert!(self.visited_down.is_visited(meeting_node));
 
-        let mut path: Vec<NodeId> = Vec::with_capacity(meeting_rank.pow(2));
+        let mut path: Vec<EdgeId> = Vec::with_capacity(meeting_rank.pow(2));
 
         let up_edge = self.dist_up[meeting_node];
         let down_edge = self.dist_down[meeting_node];
 
-        path.push(meeting_node);
         if up_edge.1.is_some() {
             self.walk_down(up_edge.1.unwrap(), true, &mut path, edges);
             path.reverse();
@@ -178,7 +181,7 @@ impl Dijkstra {
     }
 
     // walk shortcuts from meeting point to end
-    fn walk_down(&self, edge: EdgeId, is_upwards: bool, path: &mut Vec<NodeId>, edges: &[Way]) {
+    fn walk_down(&self, edge: EdgeId, is_upwards: bool, path: &mut Vec<EdgeId>, edges: &[Way]) {
         resolve_edge(edge, path, is_upwards, edges);
 
         let current_edge = edges[edge];
@@ -243,7 +246,7 @@ impl Dijkstra {
 fn resolve_edge(
     // &self,
     edge: EdgeId,
-    path: &mut Vec<NodeId>,
+    path: &mut Vec<EdgeId>,
     is_upwards: bool,
     edges: &[Way],
 ) {
@@ -257,12 +260,6 @@ fn resolve_edge(
                 resolve_edge(*next, path, is_upwards, edges);
             }
         }
-        _ => {
-            if is_upwards {
-                path.push(edges[edge].source)
-            } else {
-                path.push(edges[edge].target)
-            }
-        }
+        _ => path.push(edge),
     }
 }
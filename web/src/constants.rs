@@ -3,8 +3,10 @@ pub type EdgeId = usize;
 pub type Weight = usize;
 pub type Rank = usize;
 pub type GridId = usize;
+pub type WayId = usize;
 
 pub const INVALID_NODE: NodeId = std::usize::MAX;
+pub const INVALID_WAY: WayId = usize::MAX;
 pub const WEIGHT_MAX: Weight = std::usize::MAX;
 pub const INVALID_RANK: NodeId = std::usize::MAX;
 
@@ -11,6 +11,18 @@ pub struct Point {
 #[derive(Deserialize, Serialize, Debug)]
 pub struct Property {
     pub weight: String,
+    /// names of the used roads
+    #[serde(default)]
+    pub roads: Vec<String>,
+    /// elevation in meters of every coordinate (empty without elevation data)
+    #[serde(default)]
+    pub elevations: Vec<Option<f32>>,
+    /// total ascent in meters
+    #[serde(default)]
+    pub ascent: f32,
+    /// the route uses a ferry or car-shuttle train
+    #[serde(default)]
+    pub ferry: bool,
 }
 
 // request are two points
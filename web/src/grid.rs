@@ -38,6 +38,26 @@ pub fn get_closest_point(
     closeset
 }
 
+/// get all nodes standing for the same original node (in edge-based graphs one per incoming edge)
+pub fn get_equivalent_nodes(
+    node_id: NodeId,
+    nodes: &[Node],
+    grid: &[NodeId],
+    grid_offset: &[GridId],
+    grid_bounds: &GridBounds,
+    original_nodes: &[NodeId],
+) -> Vec<NodeId> {
+    if original_nodes.is_empty() || node_id == INVALID_NODE {
+        return vec![node_id];
+    }
+    // equivalent nodes share their coordinates and therefore the cell
+    let grid_id = get_grid_id(&nodes[node_id], grid_bounds);
+    get_points_from_cells(&[grid_id], grid, grid_offset)
+        .into_iter()
+        .filter(|other| original_nodes[*other] == original_nodes[node_id])
+        .collect()
+}
+
 /// get close node_ids
 fn get_adjacent_nodes(
     node: Node,
@@ -164,7 +184,6 @@ fn calculate_grid_id(lat_index: usize, lng_index: usize) -> GridId {
     lng_index * LAT_GRID_AMOUNT + lat_index
 }
 
-#[allow(dead_code)]
 fn get_grid_id(node: &Node, grid_bounds: &GridBounds) -> GridId {
     let lat_index = get_grid_lat(node, grid_bounds);
     let lng_index = get_grid_lng(node, grid_bounds);
@@ -190,6 +209,23 @@ fn calc_distance(a: Node, b: Node) -> f32 {
 }
 
 /// converts node ids to nodes
-pub fn get_coordinates(path: Vec<NodeId>, nodes: &[Node]) -> Vec<Node> {
-    return path.par_iter().map(|x| nodes[*x]).collect::<Vec<Node>>();
+/// coordinates (longitude, latitude) of a path of original edges, including their shape
+pub fn get_coordinates(
+    path: &[EdgeId],
+    nodes: &[Node],
+    edges: &[Way],
+    geometry: &Geometry,
+) -> Vec<(f32, f32)> {
+    let mut result = Vec::<(f32, f32)>::new();
+    if let Some(first) = path.first() {
+        let source = &nodes[edges[*first].source];
+        result.push((source.longitude, source.latitude));
+    }
+    for edge in path {
+        let shape = geometry.get(*edge).iter();
+        result.extend(shape.map(|(latitude, longitude)| (*longitude, *latitude)));
+        let target = &nodes[edges[*edge].target];
+        result.push((target.longitude, target.latitude));
+    }
+    result
 }
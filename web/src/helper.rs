@@ -19,3 +19,64 @@ pub fn read_from_disk(filename: String) -> FmiFile {
     let mut reader = BufReader::new(File::open(filename).unwrap());
     deserialize_from(&mut reader).unwrap()
 }
+
+/// names of the roads along a path of original edges, without repeating the same road
+pub fn get_road_names(path: &[EdgeId], data: &FmiFile) -> Vec<String> {
+    let mut result = Vec::<String>::new();
+    for edge in path {
+        let way = match data.edge_ways.get(*edge) {
+            Some(way) if *way != INVALID_WAY => &data.ways[*way],
+            _ => continue,
+        };
+        let name = &data.strings[way.name];
+        let reference = &data.strings[way.reference];
+        let road = match (name.is_empty(), reference.is_empty()) {
+            (false, false) => format!("{} ({})", name, reference),
+            (false, true) => name.to_string(),
+            (true, false) => reference.to_string(),
+            (true, true) => continue,
+        };
+        if result.last() != Some(&road) {
+            result.push(road);
+        }
+    }
+    result
+}
+
+/// check if a path of original edges uses a ferry
+pub fn uses_ferry(path: &[EdgeId], data: &FmiFile) -> bool {
+    path.iter().any(|edge| match data.edge_ways.get(*edge) {
+        Some(way) if *way != INVALID_WAY => data.ways[*way].ferry,
+        _ => false,
+    })
+}
+
+/// elevations along a path of original edges in the order of its coordinates
+/// (empty without elevation data)
+pub fn get_elevation_profile(path: &[EdgeId], data: &FmiFile) -> Vec<Option<f32>> {
+    let mut result = Vec::<Option<f32>>::new();
+    if data.elevations.is_empty() {
+        return result;
+    }
+    if let Some(first) = path.first() {
+        result.push(data.elevations[data.edges[*first].source]);
+    }
+    for edge in path {
+        result.extend(data.geometry.get_elevations(*edge));
+        result.push(data.elevations[data.edges[*edge].target]);
+    }
+    result
+}
+
+/// sum of all climbs in meters, missing elevations are skipped
+pub fn get_total_ascent(elevations: &[Option<f32>]) -> f32 {
+    let mut ascent = 0.0;
+    let mut previous: Option<f32> = None;
+    for elevation in elevations.iter().flatten() {
+        if let Some(previous) = previous {
+            ascent += (elevation - previous).max(0.0);
+        }
+        previous = Some(*elevation);
+    }
+    ascent
+}
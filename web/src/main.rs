@@ -69,14 +69,31 @@ async fn shortest_path(
     );
     debug!("start_id {}", start_id);
     debug!("end_id {}", end_id);
+    // edge-based graphs can be entered and left through every incoming edge
+    let start_ids = grid::get_equivalent_nodes(
+        start_id,
+        &data.nodes,
+        &data.grid,
+        &data.grid_offset,
+        &data.grid_bounds,
+        &data.original_nodes,
+    );
+    let end_ids = grid::get_equivalent_nodes(
+        end_id,
+        &data.nodes,
+        &data.grid,
+        &data.grid_offset,
+        &data.grid_bounds,
+        &data.original_nodes,
+    );
     info!(" Get node-ID in: {:?}", grid_time.elapsed());
 
     let mut dijkstra = dijkstra_cell.borrow_mut();
 
     let dijkstra_time = Instant::now();
     let tmp = dijkstra.find_path(
-        start_id,
-        end_id,
+        &start_ids,
+        &end_ids,
         &data.nodes,
         &data.edges,
         &data.up_offset,
@@ -87,13 +104,15 @@ async fn shortest_path(
 
     let result: Vec<(f32, f32)>;
     let mut cost: String = "".to_string();
+    let mut roads = Vec::<String>::new();
+    let mut elevations = Vec::<Option<f32>>::new();
+    let mut ferry = false;
     match tmp {
         Some((path, path_cost)) => {
-            let nodes = grid::get_coordinates(path, &data.nodes);
-            result = nodes
-                .par_iter()
-                .map(|node| (node.longitude, node.latitude))
-                .collect::<Vec<(f32, f32)>>();
+            result = grid::get_coordinates(&path, &data.nodes, &data.edges, &data.geometry);
+            roads = helper::get_road_names(&path, &data);
+            elevations = helper::get_elevation_profile(&path, &data);
+            ferry = helper::uses_ferry(&path, &data);
             match data.optimized_by {
                 OptimizeBy::Time => {
                     if path_cost.trunc() >= 1.0 {
@@ -127,7 +146,13 @@ async fn shortest_path(
                 r#type: "LineString".to_string(),
                 coordinates: result,
             },
-            properties: Some(Property { weight: cost }),
+            properties: Some(Property {
+                weight: cost,
+                roads,
+                ascent: helper::get_total_ascent(&elevations),
+                elevations,
+                ferry,
+            }),
         }],
     })
 }
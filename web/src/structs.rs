@@ -32,6 +32,46 @@ pub struct GridBounds {
     pub lng_max: f32,
 }
 
+/// intermediate coordinates (latitude, longitude) of the edges.
+/// the ones of edge i are coordinates[offset[i]..offset[i + 1]]
+#[derive(Deserialize, Clone)]
+pub struct Geometry {
+    pub offset: Vec<usize>,
+    pub coordinates: Vec<(f32, f32)>,
+    /// elevation in meters of every coordinate (empty without elevation data)
+    pub elevations: Vec<Option<f32>>,
+}
+
+impl Geometry {
+    /// coordinates of an edge (empty for unknown edges)
+    pub fn get(&self, edge: EdgeId) -> &[(f32, f32)] {
+        if edge + 1 >= self.offset.len() {
+            return &[];
+        }
+        &self.coordinates[self.offset[edge]..self.offset[edge + 1]]
+    }
+
+    /// elevations of the coordinates of an edge (empty without elevation data)
+    pub fn get_elevations(&self, edge: EdgeId) -> &[Option<f32>] {
+        if edge + 1 >= self.offset.len() || self.elevations.is_empty() {
+            return &[];
+        }
+        &self.elevations[self.offset[edge]..self.offset[edge + 1]]
+    }
+}
+
+/// information about an osm-way, the strings are interned
+#[allow(dead_code)]
+#[derive(Deserialize, Debug, Clone)]
+pub struct WayInfo {
+    pub osm_id: i64,
+    pub name: usize,
+    pub reference: usize,
+    pub highway: usize,
+    /// ferry or car-shuttle route
+    pub ferry: bool,
+}
+
 #[derive(Deserialize, Clone)]
 pub struct FmiFile {
     pub nodes: Vec<Node>,
@@ -43,4 +83,15 @@ pub struct FmiFile {
     pub grid: Vec<NodeId>,
     pub grid_bounds: GridBounds,
     pub optimized_by: OptimizeBy,
+    /// only for edge-based graphs: original node reached by each node
+    pub original_nodes: Vec<NodeId>,
+    /// shape of the original edges (empty for shortcuts)
+    pub geometry: Geometry,
+    /// elevation in meters of every node (empty without elevation data)
+    pub elevations: Vec<Option<f32>>,
+    /// way of every edge (INVALID_WAY for shortcuts)
+    pub edge_ways: Vec<WayId>,
+    pub ways: Vec<WayInfo>,
+    /// interned names, refs and highway classes of the ways
+    pub strings: Vec<String>,
 }